@@ -9,10 +9,30 @@ CREATE TABLE IF NOT EXISTS mypip_connection_info (
     conn_addr                  TEXT        NOT NULL,
     conn_passwd                TEXT        NOT NULL,
     conn_timeout               INTEGER     NOT NULL,
+    min_idle                   INTEGER,
+    idle_timeout_sec           INTEGER,
+    max_lifetime_sec           INTEGER,
+    acquire_timeout_ms         INTEGER,
 
     odbc_driver                TEXT,
     odbc_current_time_query    TEXT,
     odbc_current_time_col_name TEXT,
+    odbc_connection_string     TEXT,
+    odbc_dsn                   TEXT,
+    odbc_attributes            TEXT,
+
+    health_interval_sec        INTEGER,
+    health_failure_threshold   INTEGER,
+    health_ping_query          TEXT,
+
+    replica_addr               TEXT,
+    target_session_attrs       TEXT,
+
+    tls_mode                   TEXT,
+    tls_ca_file                TEXT,
+    tls_cert_file              TEXT,
+    tls_key_file               TEXT,
+    tls_server_name            TEXT,
 
     PRIMARY KEY(identifier, "name" )
 );
@@ -20,10 +40,12 @@ CREATE TABLE IF NOT EXISTS mypip_connection_info (
 CREATE TABLE IF NOT EXISTS mypip_plan_chain_bind_param (
     id         BIGINT      NOT NULL,
     chain_id        TEXT        NOT NULL,
-    idx             BIGINT      NOT NULL,
+    idx             BIGINT,
     "key"             TEXT        NOT NULL,
     bind_id         TEXT        NOT NULL,
     "row"             BIGINT,
+    "name"            TEXT,
+    "cast"            TEXT,
 
     PRIMARY KEY(id, chain_id)
 );
@@ -32,7 +54,9 @@ CREATE TABLE IF NOT EXISTS mypip_plan_chain_args (
     id         BIGINT      NOT NULL,
     chain_id        TEXT        NOT NULL,
     "data"            TEXT        NOT NULL,
-    idx             BIGINT      NOT NULL,
+    idx             BIGINT,
+    "name"            TEXT,
+    "type"            TEXT,
 
     PRIMARY KEY(id, chain_id)
 );
@@ -53,7 +77,44 @@ CREATE TABLE IF NOT EXISTS mypip_plan_chain (
     plan_id         INT         NOT NULL,
     next_chain_id        TEXT        NOT NULL,
     connection      TEXT        NOT NULL,
-    query           TEXT        NOT NULL
+    query           TEXT        NOT NULL,
+    when_expr       TEXT,
+    mode            TEXT,
+    batch_size      INT,
+    source_row_col  TEXT,
+    tx_group        TEXT,
+    kind            TEXT,
+    script_lang     TEXT,
+    script_file     TEXT,
+    script_func     TEXT,
+    after_ids       TEXT,
+    bind_mode       TEXT,
+    lookup_key      TEXT,
+    sink_step       TEXT,
+    sink_format     TEXT,
+    sink_path       TEXT,
+    sink_columns    TEXT,
+    sink_gzip       BOOLEAN,
+    sink_rotate_rows INT,
+    assert_step     TEXT,
+    assert_min_rows INT,
+    assert_max_rows INT,
+    assert_not_null TEXT,
+    assert_unique   TEXT,
+    assert_range    TEXT,
+    assert_freshness_column TEXT,
+    assert_freshness_sec INT,
+    paginate_mode   TEXT,
+    page_size       INT,
+    paginate_column TEXT,
+    paginate_initial TEXT,
+    paginate_type   TEXT,
+    redis_rule      TEXT,
+    on_row_error    TEXT,
+    dead_letter_connection TEXT,
+    dead_letter_table TEXT,
+    dead_letter_path TEXT,
+    read_only       BOOLEAN
 );
 
 CREATE INDEX mypip_plan_chain_i1 ON mypip_plan_chain(plan_id);
@@ -74,8 +135,14 @@ CREATE TABLE IF NOT EXISTS mypip_plan (
     "name"              TEXT        NOT NULL,
     type_name           TEXT        NOT NULL,
     "enable"              BOOLEAN     NOT NULL DEFAULT TRUE,
+    "transaction"         BOOLEAN     NOT NULL DEFAULT FALSE,
     interval_connection TEXT,
     interval_second     BIGINT      NOT NULL,
+    watermark_step      TEXT,
+    watermark_column    TEXT,
+    watermark_store     TEXT,
+    watermark_path      TEXT,
+    watermark_initial   TEXT,
 
     PRIMARY KEY(identifier, id)
 );
@@ -95,3 +162,12 @@ CREATE TABLE IF NOT EXISTS mypip_plan_toml (
   "enable" bool not null,
   CONSTRAINT "mypip_plan_toml_pkey" PRIMARY KEY ("identifier", "name")
 );
+
+CREATE TABLE IF NOT EXISTS mypip_plan_watermark (
+    identifier      TEXT        NOT NULL,
+    plan_name       TEXT        NOT NULL,
+    "value"         TEXT        NOT NULL,
+    updated_at      TIMESTAMP   NOT NULL,
+
+    PRIMARY KEY(identifier, plan_name)
+);
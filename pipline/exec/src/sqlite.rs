@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use rusqlite::{Connection, OpenFlags};
+use rusqlite::types::{Value, ValueRef};
+use common_rs::c_core::collection::pool::get_thread_safe_pool;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::*;
+
+const CURRENT_TIME_QUERY : &'static str = "SELECT CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)";
+
+pub struct SqlitePairExecutor {
+    conn : Connection
+}
+
+fn convert_param(param : &'_ PairValueEnum) -> Result<Value, CommonError> {
+    let v = match param {
+        PairValueEnum::Null => Value::Null,
+        PairValueEnum::Int(i) => Value::Integer(*i as i64),
+        PairValueEnum::BigInt(bi) => Value::Integer(*bi),
+        PairValueEnum::Double(d) => Value::Real(*d),
+        PairValueEnum::Float(f) => Value::Real(*f as f64),
+        PairValueEnum::Bool(b) => Value::Integer(*b as i64),
+        PairValueEnum::String(s) => Value::Text(s.clone()),
+        PairValueEnum::Bin(bin) => Value::Blob(bin.clone()),
+        other => {
+            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("sqlite not support bind {:?}", other)).to_result();
+        }
+    };
+    Ok(v)
+}
+
+fn convert_column(v : ValueRef<'_>) -> PairValueEnum {
+    match v {
+        ValueRef::Null => PairValueEnum::Null,
+        ValueRef::Integer(i) => PairValueEnum::BigInt(i),
+        ValueRef::Real(d) => PairValueEnum::Double(d),
+        ValueRef::Text(t) => PairValueEnum::String(String::from_utf8_lossy(t).to_string()),
+        ValueRef::Blob(b) => PairValueEnum::Bin(b.to_vec())
+    }
+}
+
+// a misspelled path must fail instead of creating an empty db, creation needs an explicit file:...?mode=rwc uri
+fn get_open_flags(path : &'_ str) -> OpenFlags {
+    let create = path.starts_with("file:") && path.split_once('?').map_or(false, |(_, q)| {
+        q.split('&').any(|x| x == "mode=rwc")
+    });
+
+    match create {
+        true => OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE | OpenFlags::SQLITE_OPEN_URI,
+        false => OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_URI
+    }
+}
+
+impl SqlitePairExecutor {
+    pub fn new(info : &'_ PairExecutorInfo) -> Result<Self, CommonError> {
+        let path = info.addr.first().map(|x| x.as_str()).unwrap_or(info.name.as_str());
+        if path == "" {
+            return CommonError::new(&CommonDefaultErrorKind::NoData, "sqlite path not exists").to_result();
+        }
+
+        let conn = Connection::open_with_flags(path, get_open_flags(path)).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("sqlite open failed {} : {}", path, e))
+        })?;
+        conn.busy_timeout(Duration::from_secs(info.timeout_sec as u64)).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ConnectFail, format!("sqlite busy timeout failed : {}", e))
+        })?;
+
+        Ok(SqlitePairExecutor { conn })
+    }
+}
+
+impl PairExecutor for SqlitePairExecutor {
+    fn execute_pair(&mut self, query : &'_ str, param : &'_ PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        let params = match param {
+            PairValueEnum::Array(arr) => arr.iter().map(convert_param).collect::<Result<Vec<Value>, CommonError>>()?,
+            PairValueEnum::Null => Vec::new(),
+            other => vec![convert_param(other)?]
+        };
+
+        let mut stmt = self.conn.prepare(query).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("sqlite prepare failed : {}", e))
+        })?;
+
+        if stmt.column_count() == 0 {
+            stmt.execute(rusqlite::params_from_iter(params.iter())).map_err(|e| {
+                CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("sqlite execute failed : {}", e))
+            })?;
+            return Ok(PairValueEnum::Null);
+        }
+
+        let names : Vec<String> = stmt.column_names().iter().map(|x| x.to_string()).collect();
+        let mut cols : Vec<Vec<PairValueEnum>> = names.iter().map(|_| Vec::new()).collect();
+
+        let mut rows = stmt.query(rusqlite::params_from_iter(params.iter())).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("sqlite query failed : {}", e))
+        })?;
+        while let Some(row) = rows.next().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("sqlite fetch failed : {}", e))
+        })? {
+            for (idx, col) in cols.iter_mut().enumerate() {
+                let v = row.get_ref(idx).map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("sqlite get column failed : {}", e))
+                })?;
+                col.push(convert_column(v));
+            }
+        }
+
+        let map : HashMap<String, PairValueEnum> = names.into_iter()
+            .zip(cols.into_iter().map(PairValueEnum::Array))
+            .collect();
+        Ok(PairValueEnum::Map(map))
+    }
+
+    fn get_current_time(&mut self) -> Result<Duration, CommonError> {
+        let millis : i64 = self.conn.query_row(CURRENT_TIME_QUERY, [], |r| r.get(0)).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("sqlite current time failed : {}", e))
+        })?;
+
+        Ok(Duration::from_millis(millis as u64))
+    }
+}
+
+pub fn create_sqlite_pair_conn_pool(name : String, info : PairExecutorInfo, max : usize) -> PairExecutorPool {
+    let gen_fn : Box<dyn Fn(()) -> Result<Box<dyn PairExecutor>, CommonError>> = Box::new(move |_ : ()| {
+        let exec = SqlitePairExecutor::new(&info).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ConnectFail, "sqlite executor create failed", e)
+        })?;
+        Ok(Box::new(exec) as Box<dyn PairExecutor>)
+    });
+
+    get_thread_safe_pool(name, gen_fn, max)
+}
@@ -3,7 +3,16 @@ pub const CONN_TYPE_SCYLLA : &'static str = "scylla";
 pub const CONN_TYPE_DUCKDB : &'static str = "duckdb";
 pub const CONN_TYPE_REDIS : &'static str = "redis";
 pub const CONN_TYPE_ODBC : &'static str = "odbc";
+pub const CONN_TYPE_SQLITE : &'static str = "sqlite";
+pub const SESSION_ATTRS_ANY : &'static str = "any";
+pub const SESSION_ATTRS_READ_WRITE : &'static str = "read-write";
+pub const SESSION_ATTRS_PRIMARY : &'static str = "primary";
+pub const SESSION_ATTRS_READ_ONLY : &'static str = "read-only";
+pub const SESSION_ATTRS_STANDBY : &'static str = "standby";
+pub const SESSION_ATTRS_PREFER_STANDBY : &'static str = "prefer-standby";
 pub const PLAN_TYPE_SCRIPT : &'static str = "script";
 pub const LOADER_TYPE_FILE : &'static str = "file";
 pub const LOADER_TYPE_DB : &'static str = "db";
-pub const LOADER_TYPE_DB_TOML : &'static str = "db_toml";
\ No newline at end of file
+pub const LOADER_TYPE_DB_TOML : &'static str = "db_toml";
+pub const WATERMARK_STORE_DB : &'static str = "db";
+pub const WATERMARK_STORE_FILE : &'static str = "file";
\ No newline at end of file
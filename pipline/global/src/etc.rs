@@ -14,6 +14,16 @@ pub(crate) enum InterpreterType {
     LUA
 }
 
+impl InterpreterType {
+    pub fn from_lang(lang : &'_ str) -> Option<(&'static str, InterpreterType)> {
+        match lang {
+            "lua" => Some(("lua", InterpreterType::LUA)),
+            "python" => Some(("python", InterpreterType::PYTHON)),
+            _ => None
+        }
+    }
+}
+
 impl ToString for InterpreterType {
     fn to_string(&self) -> String {
         match self {
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::*;
+use common_rs::logger::*;
+use mypip_types::config::conn::ConnectionHealthInfo;
+
+pub(crate) struct ConnCircuit {
+    failures : AtomicU32,
+    open : AtomicBool,
+    last_probe_sec : AtomicU64,
+}
+
+impl ConnCircuit {
+    pub fn new() -> Arc<Self> {
+        Arc::new(ConnCircuit {
+            failures : AtomicU32::new(0),
+            open : AtomicBool::new(false),
+            last_probe_sec : AtomicU64::new(0),
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.load(Ordering::SeqCst)
+    }
+
+    pub fn report(&self, name : &'_ str, ok : bool, health : &'_ ConnectionHealthInfo) {
+        if ok {
+            self.failures.store(0, Ordering::SeqCst);
+            if self.open.swap(false, Ordering::SeqCst) {
+                log_info!("global", "connection circuit closed, name={}", name);
+            }
+            return;
+        }
+
+        let failures = self.failures.fetch_add(1, Ordering::SeqCst) + 1;
+        if failures >= health.failure_threshold && !self.open.swap(true, Ordering::SeqCst) {
+            log_error!("global", "connection open-circuit, name={} failures={}", name, failures);
+        }
+    }
+
+    pub fn is_probe_due(&self, now_sec : u64, health : &'_ ConnectionHealthInfo) -> bool {
+        let last = self.last_probe_sec.load(Ordering::SeqCst);
+        if now_sec < last + health.interval_sec {
+            return false;
+        }
+        self.last_probe_sec.compare_exchange(last, now_sec, Ordering::SeqCst, Ordering::SeqCst).is_ok()
+    }
+}
+
+pub(crate) fn probe_connection(pool : &'_ PairExecutorPool, health : &'_ ConnectionHealthInfo) -> Result<(), CommonError> {
+    let mut item = pool.get_owned(()).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "probe get item failed", e)
+    })?;
+
+    let ret = match health.ping_query.as_ref() {
+        Some(q) => item.get_value().execute_pair(q.as_str(), &PairValueEnum::Array(Vec::new())).map(|_| ()),
+        None => item.get_value().get_current_time().map(|_| ())
+    };
+
+    match ret {
+        Ok(_) => {
+            item.restoration();
+            Ok(())
+        },
+        Err(e) => {
+            item.dispose();
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "probe failed", e).to_result()
+        }
+    }
+}
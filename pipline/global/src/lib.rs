@@ -1,5 +1,9 @@
 pub mod constant;
 mod etc;
+mod health;
+mod odbc;
+mod pool;
+mod route;
 
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -15,94 +19,193 @@ use common_rs::exec::pg::create_pg_pair_conn_pool;
 use common_rs::exec::redis::create_redis_pair_conn_pool;
 use common_rs::exec::scylla::create_scylla_pair_conn_pool;
 use common_rs::exec::odbc::create_odbc_pair_conn_pool;
+use mypip_exec::sqlite::create_sqlite_pair_conn_pool;
+use mypip_loader::tls::create_tls_extend;
+use mypip_loader::secret::{resolve_secret, SecretKey};
 use common_rs::init::{InitConfig, LoggerConf, convert_str_to_log_level};
+use common_rs::logger::*;
 use mypip_loader::{toml_file_loader, pair_db_loader};
-use mypip_types::config::app::AppConfig;
+use mypip_types::config::app::{AppConfig, AppInterpreterConfig};
 use mypip_types::interface::ConfLoader;
 use mypip_types::typealias::InterpreterPool;
-use mypip_types::config::conn::ConnectionInfos;
+use mypip_types::config::conn::{ConnectionHealthInfo, ConnectionInfo, ConnectionInfos};
+
+use mypip_types::config::plan::{Plan, PlanRoot, PlanWatermark};
+use crate::etc::InterpreterType;
+use crate::health::ConnCircuit;
+use crate::route::ConnRoute;
+use crate::pool::PoolState;
+
+fn create_exec_pool(conn_info : ConnectionInfo) -> Result<PairExecutorPool, CommonError> {
+    match conn_info.conn_type.as_str() {
+        constant::CONN_TYPE_PG => Ok(create_pg_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
+            addr: conn_info.conn_addr,
+            name: conn_info.conn_name,
+            user: conn_info.conn_user,
+            password: conn_info.conn_passwd,
+            timeout_sec: conn_info.conn_timeout,
+            extend : create_tls_extend(conn_info.tls.as_ref())
+        }, conn_info.max_size)),
+        constant::CONN_TYPE_SCYLLA => Ok(create_scylla_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
+            addr: conn_info.conn_addr,
+            name: conn_info.conn_name,
+            user: conn_info.conn_user,
+            password: conn_info.conn_passwd,
+            timeout_sec: conn_info.conn_timeout,
+            extend : create_tls_extend(conn_info.tls.as_ref())
+        }, conn_info.max_size)),
+        constant::CONN_TYPE_DUCKDB => Ok(create_duckdb_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
+            addr: conn_info.conn_addr,
+            name: conn_info.conn_name,
+            user: conn_info.conn_user,
+            password: conn_info.conn_passwd,
+            timeout_sec: conn_info.conn_timeout,
+            extend : None
+        }, conn_info.max_size)),
+        constant::CONN_TYPE_REDIS => Ok(create_redis_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
+            addr: conn_info.conn_addr,
+            name: conn_info.conn_name,
+            user: conn_info.conn_user,
+            password: conn_info.conn_passwd,
+            timeout_sec: conn_info.conn_timeout,
+            extend : create_tls_extend(conn_info.tls.as_ref())
+        }, conn_info.max_size)),
+        constant::CONN_TYPE_ODBC => Ok(create_odbc_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
+            addr: vec![],
+            name: String::from(""),
+            user: String::from(""),
+            password: String::from(""),
+            timeout_sec: conn_info.conn_timeout,
+            extend : if let Some(odbc_info) = conn_info.odbc.as_ref() {
+                let data_source = odbc::create_odbc_data_source(&conn_info, odbc_info)?;
+                Some(vec![data_source, odbc_info.current_time_query.clone(), odbc_info.current_time_col_name.clone()])
+            } else {
+                return CommonError::new(&CommonDefaultErrorKind::NoData, "ODBC INFO not exists").to_result();
+            }
+        }, conn_info.max_size)),
+        constant::CONN_TYPE_SQLITE => Ok(create_sqlite_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
+            addr: conn_info.conn_addr,
+            name: conn_info.conn_name,
+            user: conn_info.conn_user,
+            password: conn_info.conn_passwd,
+            timeout_sec: conn_info.conn_timeout,
+            extend : None
+        }, conn_info.max_size)),
+        _ => Err(CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support {}", conn_info.conn_type)))
+    }
+}
+
+struct ConnBuild {
+    conn_info : ConnectionInfo,
+    pool : PairExecutorPool,
+    route : Option<Arc<ConnRoute>>,
+}
+
+fn build_conn(conn_key : &'_ str, conn_info : ConnectionInfo) -> Result<ConnBuild, CommonError> {
+    let route = ConnRoute::new(&conn_info).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("global store create route fail {}", conn_key), e)
+    })?;
+    let pool = match route.as_ref() {
+        Some(r) => r.get(false),
+        None => create_exec_pool(conn_info.clone()).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("global store create pool fail {}", conn_key), e)
+        })?
+    };
+
+    if let Some(min_idle) = conn_info.min_idle.filter(|x| *x > 0) {
+        pool::warm_up_pool(conn_key, &pool, min_idle.min(conn_info.max_size));
+    }
+    Ok(ConnBuild { conn_info, pool, route })
+}
+
+struct DbPoolReload {
+    removed : Vec<String>,
+    builds : Vec<(String, ConnBuild, bool)>,
+}
+
+fn build_db_pool(current : &'_ HashMap<String, ConnectionInfo>, loader : &'_ dyn ConfLoader) -> Result<DbPoolReload, CommonError> {
+    let data : ConnectionInfos = loader.load_connection().map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, "global store load fail conn",e)
+    })?;
+
+    let removed : Vec<String> = current.keys().filter(|x| !data.connection.contains_key(*x)).cloned().collect();
+    let mut builds = Vec::with_capacity(data.connection.len());
+    for (conn_key, conn_info) in data.connection {
+        let is_changed = match current.get(&conn_key) {
+            Some(old) if old == &conn_info => continue,
+            Some(_) => true,
+            None => false
+        };
+
+        let build = build_conn(conn_key.as_str(), conn_info)?;
+        builds.push((conn_key, build, is_changed));
+    }
 
-use mypip_types::config::plan::{Plan, PlanRoot};
-use crate::etc::InterpreterType::{LUA, PYTHON};
+    Ok(DbPoolReload { removed, builds })
+}
 
 #[derive(Default)]
 struct GlobalStore {
     exec_pool_map : HashMap<String, PairExecutorPool>,
+    conn_info_map : HashMap<String, ConnectionInfo>,
+    circuit_map : HashMap<String, Arc<ConnCircuit>>,
+    route_map : HashMap<String, Arc<ConnRoute>>,
+    pool_state_map : HashMap<String, Arc<PoolState>>,
     exec_interpreter_map : HashMap<&'static str, InterpreterPool>,
+    interpreter_conf : AppInterpreterConfig,
     script_data_map : HashMap<String, String>,
     plans : PlanRoot,
 }
 
 impl GlobalStore {
 
-    fn reset_db_pool(&mut self, loader : &'_ dyn ConfLoader) -> Result<(), CommonError> {
-        let data : ConnectionInfos = loader.load_connection().map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, "global store load fail conn",e)
-        })?;
-        for info in data.connection {
-            let conn_key = info.0;
-            let conn_info = info.1;
-            if self.exec_pool_map.contains_key(&conn_key) {continue}
-
-            let p = match conn_info.conn_type.as_str() {
-                constant::CONN_TYPE_PG => Ok(create_pg_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
-                    addr: conn_info.conn_addr,
-                    name: conn_info.conn_name,
-                    user: conn_info.conn_user,
-                    password: conn_info.conn_passwd,
-                    timeout_sec: conn_info.conn_timeout,
-                    extend : None
-                }, conn_info.max_size)),
-                constant::CONN_TYPE_SCYLLA => Ok(create_scylla_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
-                    addr: conn_info.conn_addr,
-                    name: conn_info.conn_name,
-                    user: conn_info.conn_user,
-                    password: conn_info.conn_passwd,
-                    timeout_sec: conn_info.conn_timeout,
-                    extend : None
-                }, conn_info.max_size)),
-                constant::CONN_TYPE_DUCKDB => Ok(create_duckdb_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
-                    addr: conn_info.conn_addr,
-                    name: conn_info.conn_name,
-                    user: conn_info.conn_user,
-                    password: conn_info.conn_passwd,
-                    timeout_sec: conn_info.conn_timeout,
-                    extend : None
-                }, conn_info.max_size)),
-                constant::CONN_TYPE_REDIS => Ok(create_redis_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
-                    addr: conn_info.conn_addr,
-                    name: conn_info.conn_name,
-                    user: conn_info.conn_user,
-                    password: conn_info.conn_passwd,
-                    timeout_sec: conn_info.conn_timeout,
-                    extend : None
-                }, conn_info.max_size)),
-                constant::CONN_TYPE_ODBC => Ok(create_odbc_pair_conn_pool(conn_info.conn_name.clone(), PairExecutorInfo {
-                    addr: vec![],
-                    name: String::from(""),
-                    user: String::from(""),
-                    password: String::from(""),
-                    timeout_sec: conn_info.conn_timeout,
-                    extend : if let Some(odbc_info) = conn_info.odbc {
-                        let  addr : Vec<&'_ str> = conn_info.conn_addr[0].split(":").collect();
-                        if addr.len() < 2 {
-                            return CommonError::new(&CommonDefaultErrorKind::NoData, "ODBC addr split count <2 ").to_result();
-                        }
-
-                        let data_source = format!("Driver={{{}}};Server={};Port={};Database={};Uid={};Pwd={}",
-                            odbc_info.driver, addr[0], addr[1], conn_info.conn_name, conn_info.conn_user, conn_info.conn_passwd);
-                        Some(vec![data_source, odbc_info.current_time_query, odbc_info.current_time_col_name])
-                    } else {
-                        return CommonError::new(&CommonDefaultErrorKind::NoData, "ODBC INFO not exists").to_result();
-                    }
-                }, conn_info.max_size)),
-                _ => Err(CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support {}", conn_info.conn_type)))
-            }?;
+    fn current_conn_info(&self) -> HashMap<String, ConnectionInfo> {
+        self.conn_info_map.iter().filter(|(k, _)| self.exec_pool_map.contains_key(*k))
+            .map(|(k, v)| (k.clone(), v.clone())).collect()
+    }
 
-            self.exec_pool_map.insert(conn_key.clone(), p);
+    fn commit_conn(&mut self, conn_key : &'_ str, build : ConnBuild) {
+        let now_sec = pool::now_sec();
+        match self.pool_state_map.get(conn_key) {
+            Some(state) => {
+                if let Err(e) = state.renew(now_sec) {
+                    log_error!("global", "connection pool state renew failed, name={} {}", conn_key, e);
+                }
+            },
+            None => {
+                self.pool_state_map.insert(conn_key.to_string(), PoolState::new(now_sec));
+            }
         }
 
-        Ok(())
+        self.conn_info_map.insert(conn_key.to_string(), build.conn_info);
+        self.exec_pool_map.insert(conn_key.to_string(), build.pool);
+        match build.route {
+            Some(r) => self.route_map.insert(conn_key.to_string(), r),
+            None => self.route_map.remove(conn_key)
+        };
+    }
+
+    // every pool is built by build_db_pool before this runs, so a bad entry leaves the store untouched.
+    // old pools are only unlinked here; borrowed items hold the pool through its Arc and close it when the last one is returned.
+    fn reset_db_pool(&mut self, reload : DbPoolReload) {
+        for conn_key in reload.removed {
+            self.conn_info_map.remove(&conn_key);
+            self.exec_pool_map.remove(&conn_key);
+            self.circuit_map.remove(&conn_key);
+            self.route_map.remove(&conn_key);
+            self.pool_state_map.remove(&conn_key);
+            log_info!("global", "connection pool dropped, name={}", conn_key);
+        }
+
+        for (conn_key, build, is_changed) in reload.builds {
+            self.commit_conn(conn_key.as_str(), build);
+            self.circuit_map.insert(conn_key.clone(), ConnCircuit::new());
+            if is_changed {
+                log_info!("global", "connection pool rebuilt, name={}", conn_key);
+            } else {
+                log_info!("global", "connection pool created, name={}", conn_key);
+            }
+        }
     }
     fn reset_plan(&mut self, loader : &'_ dyn ConfLoader) -> Result<(), CommonError> {
         let load = loader.load_plan()?;
@@ -126,9 +229,54 @@ impl GlobalStore {
         self.script_data_map.extend(map);
         Ok(())
     }
-    fn reset(&mut self, loader : &'_ dyn ConfLoader) -> Result<(), CommonError> {
-        self.reset_db_pool(loader)?;
+    fn create_interpreter_pool(&mut self, lang : &'_ str) -> Result<InterpreterPool, CommonError> {
+        let (key, typ) = InterpreterType::from_lang(lang).ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support interpreter {}", lang))
+        })?;
+        if let Some(p) = self.exec_interpreter_map.get(key) {
+            return Ok(p.clone());
+        }
+
+        let conf = match typ {
+            InterpreterType::LUA => self.interpreter_conf.lua.clone(),
+            InterpreterType::PYTHON => self.interpreter_conf.python.clone()
+        }.unwrap_or_default();
+        if !conf.enable {
+            return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("interpreter {} disabled", key)).to_result();
+        }
+        if conf.pool_size == 0 {
+            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("interpreter {} pool_size must be greater than 0", key)).to_result();
+        }
+
+        mypip_interpreter::init::interpreter_lang_init(key, conf.pool_size).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::Critical, format!("interpreter {} init failed", key), e)
+        })?;
+
+        let p = crate::etc::create_interpreter_pool(typ, conf.pool_size);
+        self.exec_interpreter_map.insert(key, p.clone());
+        log_info!("global", "interpreter pool created, lang={} pool_size={}", key, conf.pool_size);
+        Ok(p)
+    }
+    fn reset_interpreter_pool(&mut self) -> Result<(), CommonError> {
+        let mut langs : HashMap<String, String> = HashMap::new();
+        for (plan_name, plan) in self.plans.plan.iter().filter(|(_, x)| x.enable) {
+            let chain_scripts = plan.chain.iter().flatten().filter_map(|x| x.script.as_ref());
+            for script in plan.script.iter().chain(chain_scripts) {
+                langs.entry(script.lang.clone()).or_insert(plan_name.clone());
+            }
+        }
+
+        for (lang, plan_name) in langs {
+            self.create_interpreter_pool(lang.as_str()).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("plan {} interpreter {} not available", plan_name, lang), e)
+            })?;
+        }
+        Ok(())
+    }
+    fn reset(&mut self, reload : DbPoolReload, loader : &'_ dyn ConfLoader) -> Result<(), CommonError> {
+        self.reset_db_pool(reload);
         self.reset_plan(loader)?;
+        self.reset_interpreter_pool()?;
         self.reset_scripts_file(loader)?;
         Ok(())
     }
@@ -136,6 +284,7 @@ impl GlobalStore {
 
 pub struct GlobalOnceLockStore {
     script_lib_base_dir: Option<String>,
+    watermark_dir : String,
 }
 pub struct GlobalImpl {
     store : Arc<RwLock<GlobalStore>>,
@@ -153,24 +302,30 @@ impl mypip_types::interface::GlobalLayoutInit for GlobalImpl {
         let config_dir = std::path::Path::new(&base_dir).join("config").join(identifier.as_str()).to_string_lossy().to_string();
         let log_dir = std::path::Path::new(&base_dir).join("log").join(identifier.as_str()).to_string_lossy().to_string();
         let script_dir = std::path::Path::new(&base_dir).join("scripts").join(identifier.as_str()).to_string_lossy().to_string();
+        let watermark_dir = std::path::Path::new(&base_dir).join("watermark").join(identifier.as_str()).to_string_lossy().to_string();
+
+        let interpreter_conf = app_config.interpreter_conf.clone().unwrap_or_default();
+        let secret_key = app_config.secret_key_file.as_ref().map(|x| SecretKey::load(x.as_str())).transpose().map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InitFailed, "load secret key failed", e)
+        })?;
 
         let new_loader : Box<dyn ConfLoader> = match loader_type.as_str() {
             constant::LOADER_TYPE_DB => {
-                pair_db_loader::rdb::PairDbLoader::new(identifier.clone(), config_dir.as_str(), once_conf_load, false).map(|l| {
+                pair_db_loader::rdb::PairDbLoader::new(identifier.clone(), config_dir.as_str(), once_conf_load, false, secret_key.clone()).map(|l| {
                     Box::new(l) as Box<dyn ConfLoader>
                 }).map_err(|e| {
                     CommonError::extend(&CommonDefaultErrorKind::Etc, "", e)
                 })
             },
             constant::LOADER_TYPE_DB_TOML => {
-                pair_db_loader::rdb::PairDbLoader::new(identifier.clone(), config_dir.as_str(), once_conf_load, true).map(|l| {
+                pair_db_loader::rdb::PairDbLoader::new(identifier.clone(), config_dir.as_str(), once_conf_load, true, secret_key.clone()).map(|l| {
                     Box::new(l) as Box<dyn ConfLoader>
                 }).map_err(|e| {
                     CommonError::extend(&CommonDefaultErrorKind::Etc, "", e)
                 })
             },
             constant::LOADER_TYPE_FILE => {
-                let ok : Result<Box<dyn ConfLoader>, CommonError> = Ok(Box::new(toml_file_loader::TomlFileConfLoader::new(config_dir, script_dir, identifier.clone(), once_conf_load)) as Box<dyn ConfLoader>);
+                let ok : Result<Box<dyn ConfLoader>, CommonError> = Ok(Box::new(toml_file_loader::TomlFileConfLoader::new(config_dir, script_dir, identifier.clone(), once_conf_load).with_secret_key(secret_key.clone())) as Box<dyn ConfLoader>);
                 ok
             },
             _ => {
@@ -202,8 +357,11 @@ impl mypip_types::interface::GlobalLayoutInit for GlobalImpl {
                         CommonError::new(&CommonDefaultErrorKind::NoData, "no db config").to_result()
                     },
                     |o| {
+                        let db_password = resolve_secret(secret_key.as_ref(), o.db_password.as_str()).map_err(|e| {
+                            CommonError::extend(&CommonDefaultErrorKind::InitFailed, "log db_password decrypt failed", e)
+                        })?;
                         Ok(
-                            LoggerConf::Scylla(identifier.clone(), o.db_address, o.db_name, o.db_user, o.db_password,
+                            LoggerConf::Scylla(identifier.clone(), o.db_address, o.db_name, o.db_user, db_password,
                                                convert_str_to_log_level(app_config.log_conf.log_level.as_str()), o.db_ttl)
                         )
                     }
@@ -220,29 +378,32 @@ impl mypip_types::interface::GlobalLayoutInit for GlobalImpl {
 
         self.once_store.get_or_init(move || {
             GlobalOnceLockStore {
-                script_lib_base_dir : app_config.script_lib
+                script_lib_base_dir : app_config.script_lib,
+                watermark_dir,
             }
         });
 
-        let mut writer = self.store.write().map_err(|e| {
-            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
-        })?;
-
         let mut store = GlobalStore {
             exec_pool_map: HashMap::new(),
+            conn_info_map: HashMap::new(),
+            circuit_map: HashMap::new(),
+            route_map: HashMap::new(),
+            pool_state_map: HashMap::new(),
             exec_interpreter_map : HashMap::new(),
+            interpreter_conf,
             plans : PlanRoot::default(),
             script_data_map: HashMap::new(),
         };
 
-        store.reset(loader.as_ref())?;
         mypip_interpreter::init::interpreter_init(self).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::Critical, "interpreter init failed", e)
         })?;
+        let reload = build_db_pool(&HashMap::new(), loader.as_ref())?;
+        store.reset(reload, loader.as_ref())?;
 
-        store.exec_interpreter_map.insert("lua", crate::etc::create_interpreter_pool(LUA,100));
-        store.exec_interpreter_map.insert("python", crate::etc::create_interpreter_pool(PYTHON, 100));
-
+        let mut writer = self.store.write().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
         *writer = store;
 
         self.once.store(true, Ordering::Relaxed);
@@ -261,12 +422,58 @@ impl mypip_types::interface::GlobalLayout for GlobalImpl {
             CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
         })?;
 
+        if let Some(route) = reader.route_map.get(name.as_ref()) {
+            return Ok(route.get(false));
+        }
+
         let opt = reader.exec_pool_map.get(&name.as_ref().to_string());
         if opt.is_none() {
             return CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists {}", name.as_ref())).to_result();
         }
         Ok(opt.unwrap().clone())
     }
+    fn get_read_exec_pool(&'static self, name : Cow<'_, str>) -> Result<PairExecutorPool, CommonError > {
+        if !self.once.load(Ordering::Relaxed) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
+        }
+
+        let route = {
+            let reader = self.store.read().map_err(|e| {
+                CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+            })?;
+            reader.route_map.get(name.as_ref()).cloned()
+        };
+
+        match route {
+            Some(r) => Ok(r.get(true)),
+            None => self.get_exec_pool(name)
+        }
+    }
+    fn failover_exec_pool(&'static self, name : Cow<'_, str>, read_only : bool) -> Result<bool, CommonError> {
+        if !self.once.load(Ordering::Relaxed) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
+        }
+
+        let reader = self.store.read().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+
+        Ok(reader.route_map.get(name.as_ref()).map_or(false, |x| x.failover(name.as_ref(), read_only)))
+    }
+    fn get_conn_info(&'static self, name : Cow<'_, str>) -> Result<ConnectionInfo, CommonError> {
+        if !self.once.load(Ordering::Relaxed) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
+        }
+
+        let reader = self.store.read().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+
+        match reader.conn_info_map.get(name.as_ref()) {
+            Some(info) => Ok(info.clone()),
+            None => CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists {}", name.as_ref())).to_result()
+        }
+    }
     fn get_plan(&'static self) -> Result<HashMap<String, Plan>, CommonError> {
         if !self.once.load(Ordering::Relaxed) {
             return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
@@ -288,11 +495,15 @@ impl mypip_types::interface::GlobalLayout for GlobalImpl {
             CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
         })?;
 
-        let opt = reader.exec_interpreter_map.get(name.as_ref());
-        if opt.is_none() {
-            return CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists {}", name.as_ref())).to_result();
+        if let Some(p) = reader.exec_interpreter_map.get(name.as_ref()) {
+            return Ok(p.clone());
         }
-        Ok(opt.unwrap().clone())
+        drop(reader);
+
+        let mut writer = self.store.write().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+        writer.create_interpreter_pool(name.as_ref())
     }
     fn close(&'static self) -> Result<(), CommonError> {
         if !self.once.load(Ordering::Relaxed) == true {
@@ -304,6 +515,11 @@ impl mypip_types::interface::GlobalLayout for GlobalImpl {
         })?;
 
         writer.exec_pool_map.clear();
+        writer.conn_info_map.clear();
+        writer.circuit_map.clear();
+        writer.route_map.clear();
+        writer.pool_state_map.clear();
+        writer.exec_interpreter_map.clear();
         mypip_interpreter::init::interpreter_exit().map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::Critical, "interpreter exit failed", e)
         })?;
@@ -315,11 +531,17 @@ impl mypip_types::interface::GlobalLayout for GlobalImpl {
             return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
         }
 
+        let loader = self.loader.get().unwrap().as_ref();
+        let current = self.store.read().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?.current_conn_info();
+        let reload = build_db_pool(&current, loader)?;
+
         let mut writer = self.store.write().map_err(|e| {
             CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
         })?;
 
-        writer.reset(self.loader.get().unwrap().as_ref())?;
+        writer.reset(reload, loader)?;
 
         Ok(())
     }
@@ -345,6 +567,181 @@ impl mypip_types::interface::GlobalLayout for GlobalImpl {
 
         Ok(s.script_lib_base_dir.clone())
     }
+
+    fn load_watermark(&'static self, plan_name : &'_ str, wm : &'_ PlanWatermark) -> Result<Option<String>, CommonError> {
+        match wm.store.as_str() {
+            constant::WATERMARK_STORE_DB => {
+                let loader = self.loader.get().ok_or_else(|| {
+                    CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized")
+                })?;
+                loader.load_watermark(plan_name)
+            },
+            constant::WATERMARK_STORE_FILE => {
+                let path = self.get_watermark_file_path(plan_name, wm)?;
+                match std::fs::read_to_string(path.as_path()) {
+                    Ok(data) => Ok(Some(data.trim().to_string())),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                    Err(e) => CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("read watermark {} failed, {}", path.display(), e)).to_result()
+                }
+            },
+            _ => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support watermark store {}", wm.store)).to_result()
+        }
+    }
+
+    fn save_watermark(&'static self, plan_name : &'_ str, wm : &'_ PlanWatermark, value : &'_ str) -> Result<(), CommonError> {
+        match wm.store.as_str() {
+            constant::WATERMARK_STORE_DB => {
+                let loader = self.loader.get().ok_or_else(|| {
+                    CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized")
+                })?;
+                loader.save_watermark(plan_name, value)
+            },
+            constant::WATERMARK_STORE_FILE => {
+                let path = self.get_watermark_file_path(plan_name, wm)?;
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir).map_err(|e| {
+                        CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("create watermark dir failed, {}", e))
+                    })?;
+                }
+
+                let tmp = path.with_extension("tmp");
+                std::fs::write(tmp.as_path(), value).and_then(|_| {
+                    std::fs::rename(tmp.as_path(), path.as_path())
+                }).map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("write watermark {} failed, {}", path.display(), e))
+                })
+            },
+            _ => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support watermark store {}", wm.store)).to_result()
+        }
+    }
+
+    fn is_conn_open_circuit(&'static self, name : Cow<'_, str>) -> Result<bool, CommonError> {
+        if !self.once.load(Ordering::Relaxed) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
+        }
+
+        let reader = self.store.read().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+
+        Ok(reader.circuit_map.get(name.as_ref()).map_or(false, |x| x.is_open()))
+    }
+
+    fn report_conn_result(&'static self, name : Cow<'_, str>, ok : bool) -> Result<(), CommonError> {
+        if !self.once.load(Ordering::Relaxed) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
+        }
+
+        let reader = self.store.read().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+
+        if let (Some(circuit), Some(info)) = (reader.circuit_map.get(name.as_ref()), reader.conn_info_map.get(name.as_ref())) {
+            circuit.report(name.as_ref(), ok, info.health.as_ref().unwrap_or(&ConnectionHealthInfo::default()));
+        }
+        Ok(())
+    }
+
+    fn acquire_conn_slot(&'static self, name : Cow<'_, str>) -> Result<bool, CommonError> {
+        if !self.once.load(Ordering::Relaxed) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
+        }
+
+        let (info, state) = {
+            let reader = self.store.read().map_err(|e| {
+                CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+            })?;
+
+            match (reader.conn_info_map.get(name.as_ref()), reader.pool_state_map.get(name.as_ref())) {
+                (Some(info), Some(state)) => (info.clone(), state.clone()),
+                _ => {
+                    return CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists {}", name.as_ref())).to_result();
+                }
+            }
+        };
+
+        state.acquire(name.as_ref(), info.max_size, info.acquire_timeout_ms)?;
+
+        let now_sec = pool::now_sec();
+        Ok(info.idle_timeout_sec.map_or(false, |x| state.is_idle_over(now_sec, x)))
+    }
+
+    fn release_conn_slot(&'static self, name : Cow<'_, str>) -> Result<(), CommonError> {
+        if !self.once.load(Ordering::Relaxed) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
+        }
+
+        let reader = self.store.read().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+
+        match reader.pool_state_map.get(name.as_ref()) {
+            Some(state) => state.release(pool::now_sec()),
+            None => Ok(())
+        }
+    }
+
+    fn probe_connections(&'static self) -> Result<(), CommonError> {
+        if !self.once.load(Ordering::Relaxed) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "not initialized").to_result();
+        }
+
+        let now_sec = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?.as_secs();
+
+        let targets = {
+            let reader = self.store.read().map_err(|e| {
+                CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+            })?;
+
+            let mut targets = Vec::with_capacity(reader.circuit_map.len());
+            for (name, circuit) in reader.circuit_map.iter() {
+                let info = match reader.conn_info_map.get(name) {
+                    Some(info) => info.clone(),
+                    None => continue
+                };
+                let health = info.health.clone().unwrap_or_default();
+                let p = match reader.route_map.get(name) {
+                    Some(route) => Some(route.get(false)),
+                    None => reader.exec_pool_map.get(name).cloned()
+                };
+                if let Some(p) = p.filter(|_| circuit.is_probe_due(now_sec, &health)) {
+                    targets.push((name.clone(), p, circuit.clone(), health, info, reader.pool_state_map.get(name).cloned()));
+                }
+            }
+            targets
+        };
+
+        for (name, p, circuit, health, info, state) in targets {
+            let ret = health::probe_connection(&p, &health);
+            if let Err(e) = ret.as_ref() {
+                log_debug!("global", "connection probe failed, name={} {}", name, e);
+            }
+            circuit.report(name.as_str(), ret.is_ok(), &health);
+
+            // connection lifetime, idle close and min_idle run on the probe schedule, not on the plan thread that checks out
+            if let Some(state) = state.filter(|_| ret.is_ok()) {
+                if let Err(e) = pool::maintain_pool(name.as_str(), &p, &state, &info, pool::now_sec()) {
+                    log_error!("global", "connection pool maintain failed, name={} {}", name, e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GlobalImpl {
+    fn get_watermark_file_path(&'static self, plan_name : &'_ str, wm : &'_ PlanWatermark) -> Result<std::path::PathBuf, CommonError> {
+        if let Some(path) = wm.path.as_ref() {
+            return Ok(std::path::PathBuf::from(path));
+        }
+
+        let s = self.once_store.get().ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::Critical, "global once store not init")
+        })?;
+        Ok(std::path::Path::new(s.watermark_dir.as_str()).join(format!("{}.watermark", plan_name)))
+    }
 }
 
 pub static GLOBAL: LazyLock<GlobalImpl> = LazyLock::new(|| {
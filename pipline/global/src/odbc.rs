@@ -0,0 +1,91 @@
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use mypip_types::config::conn::{ConnectionInfo, OdbcConnectionInfo};
+
+fn quote_attr_value(v : &'_ str) -> String {
+    if v.contains(';') || v.contains('{') || v.contains('}') || v.starts_with(' ') || v.ends_with(' ') {
+        format!("{{{}}}", v.replace('}', "}}"))
+    } else {
+        v.to_string()
+    }
+}
+
+fn split_host_port(addr : &'_ str) -> (&'_ str, &'_ str) {
+    match addr.rsplit_once(':') {
+        Some((host, port)) => (host, port),
+        None => (addr, "")
+    }
+}
+
+fn fill_template(template : &'_ str, conn_info : &'_ ConnectionInfo, odbc_info : &'_ OdbcConnectionInfo) -> Result<String, CommonError> {
+    let addr = conn_info.conn_addr.first().map(|x| x.as_str()).unwrap_or("");
+    let (host, port) = split_host_port(addr);
+
+    let mut ret = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..].find('}').map(|x| x + start).ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("unterminated odbc placeholder {}", template))
+        })?;
+        ret.push_str(&rest[..start]);
+
+        let key = &rest[start + 2..end];
+        let value = match key {
+            "driver" => odbc_info.driver.clone().unwrap_or_default(),
+            "dsn" => odbc_info.dsn.clone().unwrap_or_default(),
+            "addr" => addr.to_string(),
+            "host" => host.to_string(),
+            "port" => port.to_string(),
+            "database" => conn_info.conn_name.clone(),
+            "user" => quote_attr_value(conn_info.conn_user.as_str()),
+            "password" => quote_attr_value(conn_info.conn_passwd.as_str()),
+            other => {
+                return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("not support odbc placeholder {}", other)).to_result();
+            }
+        };
+        ret.push_str(value.as_str());
+        rest = &rest[end + 1..];
+    }
+    ret.push_str(rest);
+
+    Ok(ret)
+}
+
+pub(crate) fn create_odbc_data_source(conn_info : &'_ ConnectionInfo, odbc_info : &'_ OdbcConnectionInfo) -> Result<String, CommonError> {
+    let mut data_source = if let Some(template) = odbc_info.connection_string.as_ref() {
+        fill_template(template.as_str(), conn_info, odbc_info)?
+    } else if let Some(dsn) = odbc_info.dsn.as_ref() {
+        let mut s = format!("DSN={}", quote_attr_value(dsn));
+        if conn_info.conn_user != "" {
+            s.push_str(format!(";Uid={}", quote_attr_value(conn_info.conn_user.as_str())).as_str());
+        }
+        if conn_info.conn_passwd != "" {
+            s.push_str(format!(";Pwd={}", quote_attr_value(conn_info.conn_passwd.as_str())).as_str());
+        }
+        s
+    } else {
+        let driver = odbc_info.driver.as_ref().ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::NoData, "ODBC need driver, dsn or connection_string")
+        })?;
+        let addr : Vec<&'_ str> = conn_info.conn_addr.first().map(|x| x.split(":").collect()).unwrap_or_default();
+        if addr.len() < 2 {
+            return CommonError::new(&CommonDefaultErrorKind::NoData, "ODBC addr split count <2 ").to_result();
+        }
+
+        format!("Driver={{{}}};Server={};Port={};Database={};Uid={};Pwd={}",
+            driver, addr[0], addr[1], conn_info.conn_name, quote_attr_value(conn_info.conn_user.as_str()), quote_attr_value(conn_info.conn_passwd.as_str()))
+    };
+
+    if let Some(attrs) = odbc_info.attributes.as_ref() {
+        let mut keys : Vec<&String> = attrs.keys().collect();
+        keys.sort();
+        for k in keys {
+            if !data_source.is_empty() && !data_source.ends_with(';') {
+                data_source.push(';');
+            }
+            data_source.push_str(format!("{}={}", k, quote_attr_value(attrs[k].as_str())).as_str());
+        }
+    }
+
+    Ok(data_source)
+}
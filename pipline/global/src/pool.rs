@@ -0,0 +1,211 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::{PairExecutor, PairExecutorPool};
+use common_rs::logger::*;
+use mypip_types::config::conn::ConnectionInfo;
+
+pub(crate) fn now_sec() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |x| x.as_secs())
+}
+
+pub(crate) struct PoolState {
+    in_use : Mutex<usize>,
+    cond : Condvar,
+    last_used_sec : AtomicU64,
+    // connection id (executor address) -> open time, filled by the maintenance pass
+    conns : Mutex<HashMap<usize, u64>>,
+}
+
+// the boxed executor stays at one address for the life of the connection
+pub(crate) fn get_conn_id(conn : &'_ dyn PairExecutor) -> usize {
+    conn as *const dyn PairExecutor as *const () as usize
+}
+
+impl PoolState {
+    pub fn new(now_sec : u64) -> Arc<Self> {
+        Arc::new(PoolState {
+            in_use : Mutex::new(0),
+            cond : Condvar::new(),
+            last_used_sec : AtomicU64::new(now_sec),
+            conns : Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn acquire(&self, name : &'_ str, max_size : usize, timeout_ms : Option<u64>) -> Result<(), CommonError> {
+        let mut in_use = self.in_use.lock().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+
+        if let Some(timeout) = timeout_ms {
+            let deadline = Instant::now() + Duration::from_millis(timeout);
+            while *in_use >= max_size {
+                let remain = deadline.saturating_duration_since(Instant::now());
+                if remain.is_zero() {
+                    return CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("connection {} acquire timeout {}ms", name, timeout)).to_result();
+                }
+                in_use = self.cond.wait_timeout(in_use, remain).map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+                })?.0;
+            }
+        }
+
+        *in_use += 1;
+        Ok(())
+    }
+
+    fn try_acquire(&self, max_size : usize) -> Result<bool, CommonError> {
+        let mut in_use = self.in_use.lock().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+
+        if *in_use >= max_size {
+            return Ok(false);
+        }
+        *in_use += 1;
+        Ok(true)
+    }
+
+    fn get_in_use(&self) -> Result<usize, CommonError> {
+        Ok(*self.in_use.lock().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?)
+    }
+
+    // used_sec None keeps the idle timer, maintenance checkouts are not real use
+    fn release_slots(&self, cnt : usize, used_sec : Option<u64>) -> Result<(), CommonError> {
+        let mut in_use = self.in_use.lock().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+
+        *in_use = in_use.saturating_sub(cnt);
+        if let Some(now_sec) = used_sec {
+            self.last_used_sec.store(now_sec, Ordering::SeqCst);
+        }
+        self.cond.notify_all();
+        Ok(())
+    }
+
+    pub fn release(&self, now_sec : u64) -> Result<(), CommonError> {
+        self.release_slots(1, Some(now_sec))
+    }
+
+    pub fn is_idle_over(&self, now_sec : u64, idle_timeout_sec : u64) -> bool {
+        now_sec.saturating_sub(self.last_used_sec.load(Ordering::SeqCst)) >= idle_timeout_sec
+    }
+
+    // a rebuilt pool has new connections, the old ids mean nothing
+    pub fn renew(&self, now_sec : u64) -> Result<(), CommonError> {
+        self.last_used_sec.store(now_sec, Ordering::SeqCst);
+        self.conns.lock().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?.clear();
+        Ok(())
+    }
+}
+
+// runs from the health loop : takes every idle connection once, closes the ones over max_lifetime_sec,
+// closes idle ones over min_idle when the pool had no checkout for idle_timeout_sec, then tops up to min_idle
+pub(crate) fn maintain_pool(name : &'_ str, pool : &'_ PairExecutorPool, state : &'_ PoolState, info : &'_ ConnectionInfo, now_sec : u64) -> Result<(), CommonError> {
+    if info.min_idle.is_none() && info.idle_timeout_sec.is_none() && info.max_lifetime_sec.is_none() {
+        return Ok(());
+    }
+
+    let min_idle = info.min_idle.unwrap_or(0).min(info.max_size);
+    let is_pool_idle = info.idle_timeout_sec.map_or(false, |x| state.is_idle_over(now_sec, x));
+    let in_use = state.get_in_use()?;
+    let mut conns = state.conns.lock().map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+    })?;
+
+    let idle = conns.len().saturating_sub(in_use);
+    let mut seen = HashSet::with_capacity(idle);
+    let mut kept = Vec::new();
+    let (mut slots, mut lifetime_closed, mut idle_closed, mut opened) = (0, 0, 0, 0);
+
+    while seen.len() < idle || kept.len() < min_idle {
+        match state.try_acquire(info.max_size) {
+            Ok(true) => slots += 1,
+            Ok(false) => break,
+            Err(e) => {
+                log_error!("global", "connection maintain acquire failed, name={} {}", name, e);
+                break;
+            }
+        }
+
+        let mut item = match pool.get_owned(()) {
+            Ok(item) => item,
+            Err(e) => {
+                log_error!("global", "connection maintain get item failed, name={} {}", name, e);
+                break;
+            }
+        };
+        let id = get_conn_id(&**item.get_value());
+
+        let created = match conns.get(&id) {
+            Some(created) => *created,
+            None => {
+                // a new connection, so no idle one is left; ids not seen were closed elsewhere when nothing is in use
+                if in_use == 0 {
+                    conns.retain(|k, _| seen.contains(k));
+                }
+                if kept.len() >= min_idle {
+                    item.dispose();
+                    break;
+                }
+                conns.insert(id, now_sec);
+                seen.insert(id);
+                opened += 1;
+                kept.push(item);
+                continue;
+            }
+        };
+        seen.insert(id);
+
+        if info.max_lifetime_sec.map_or(false, |x| now_sec.saturating_sub(created) >= x) {
+            item.dispose();
+            conns.remove(&id);
+            lifetime_closed += 1;
+        } else if is_pool_idle && kept.len() >= min_idle {
+            item.dispose();
+            conns.remove(&id);
+            idle_closed += 1;
+        } else {
+            kept.push(item);
+        }
+    }
+    drop(conns);
+
+    let idle = kept.len();
+    for mut item in kept {
+        item.restoration();
+    }
+    state.release_slots(slots, None)?;
+
+    if lifetime_closed > 0 || idle_closed > 0 || opened > 0 {
+        log_info!("global", "connection pool maintained, name={} lifetime_closed={} idle_closed={} opened={} idle={}", name, lifetime_closed, idle_closed, opened, idle);
+    }
+    Ok(())
+}
+
+pub(crate) fn warm_up_pool(name : &'_ str, pool : &'_ PairExecutorPool, min_idle : usize) {
+    let mut items = Vec::with_capacity(min_idle);
+    for _ in 0..min_idle {
+        match pool.get_owned(()) {
+            Ok(item) => items.push(item),
+            Err(e) => {
+                log_error!("global", "connection warm up failed, name={} {}", name, e);
+                break;
+            }
+        }
+    }
+
+    let cnt = items.len();
+    for mut item in items {
+        item.restoration();
+    }
+    log_debug!("global", "connection warm up, name={} idle={}", name, cnt);
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairExecutorPool;
+use common_rs::logger::*;
+use mypip_types::config::conn::ConnectionInfo;
+use crate::constant;
+
+pub(crate) struct ConnRoute {
+    write : Vec<(String, PairExecutorPool)>,
+    read : Vec<(String, PairExecutorPool)>,
+    write_idx : AtomicUsize,
+    read_idx : AtomicUsize,
+}
+
+fn create_addr_pool(conn_info : &'_ ConnectionInfo, addr : &'_ str) -> Result<(String, PairExecutorPool), CommonError> {
+    let mut info = conn_info.clone();
+    info.conn_addr = vec![addr.to_string()];
+    info.topology = None;
+
+    let p = crate::create_exec_pool(info).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("create pool failed {}", addr), e)
+    })?;
+    Ok((addr.to_string(), p))
+}
+
+impl ConnRoute {
+    pub fn new(conn_info : &'_ ConnectionInfo) -> Result<Option<Arc<Self>>, CommonError> {
+        let replicas = conn_info.topology.as_ref().and_then(|x| x.replicas.clone()).unwrap_or_default();
+        if conn_info.conn_addr.len() <= 1 && replicas.is_empty() {
+            return Ok(None);
+        }
+        if conn_info.conn_type != constant::CONN_TYPE_PG {
+            if conn_info.topology.is_some() {
+                return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} not support topology", conn_info.conn_type)).to_result();
+            }
+            return Ok(None);
+        }
+
+        let attrs = conn_info.topology.as_ref().and_then(|x| x.target_session_attrs.clone())
+            .unwrap_or(String::from(constant::SESSION_ATTRS_PREFER_STANDBY));
+
+        let write = conn_info.conn_addr.iter().map(|x| create_addr_pool(conn_info, x.as_str())).collect::<Result<Vec<_>, CommonError>>()?;
+        let standby = replicas.iter().map(|x| create_addr_pool(conn_info, x.as_str())).collect::<Result<Vec<_>, CommonError>>()?;
+
+        let read = match attrs.as_str() {
+            constant::SESSION_ATTRS_READ_WRITE | constant::SESSION_ATTRS_PRIMARY => write.clone(),
+            constant::SESSION_ATTRS_READ_ONLY | constant::SESSION_ATTRS_STANDBY => standby,
+            constant::SESSION_ATTRS_PREFER_STANDBY => standby.into_iter().chain(write.iter().cloned()).collect(),
+            constant::SESSION_ATTRS_ANY => write.iter().cloned().chain(standby).collect(),
+            other => {
+                return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support target_session_attrs {}", other)).to_result();
+            }
+        };
+
+        if write.is_empty() {
+            return CommonError::new(&CommonDefaultErrorKind::NoData, "topology need primary addr").to_result();
+        }
+        if read.is_empty() {
+            return CommonError::new(&CommonDefaultErrorKind::NoData, format!("target_session_attrs {} need replicas", attrs)).to_result();
+        }
+
+        Ok(Some(Arc::new(ConnRoute {
+            write,
+            read,
+            write_idx : AtomicUsize::new(0),
+            read_idx : AtomicUsize::new(0),
+        })))
+    }
+
+    fn candidates(&self, read_only : bool) -> (&'_ Vec<(String, PairExecutorPool)>, &'_ AtomicUsize) {
+        if read_only {
+            (&self.read, &self.read_idx)
+        } else {
+            (&self.write, &self.write_idx)
+        }
+    }
+
+    pub fn get(&self, read_only : bool) -> PairExecutorPool {
+        let (list, idx) = self.candidates(read_only);
+        list[idx.load(Ordering::SeqCst) % list.len()].1.clone()
+    }
+
+    pub fn failover(&self, name : &'_ str, read_only : bool) -> bool {
+        let (list, idx) = self.candidates(read_only);
+        if list.len() <= 1 {
+            return false;
+        }
+
+        let next = (idx.fetch_add(1, Ordering::SeqCst) + 1) % list.len();
+        log_info!("global", "connection failover, name={} read_only={} addr={}", name, read_only, list[next].0);
+        true
+    }
+}
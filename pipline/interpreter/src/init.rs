@@ -1,25 +1,58 @@
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use common_rs::c_err::CommonError;
 use common_rs::c_err::gen::CommonDefaultErrorKind;
 use mypip_types::interface::GlobalLayout;
 
+static PY_INITIALIZED : AtomicBool = AtomicBool::new(false);
+static LUA_INITIALIZED : AtomicBool = AtomicBool::new(false);
+
 pub fn interpreter_exit()  -> Result<(),CommonError> {
-    crate::interpreter::py::PyInterpreterInitialization::shutdown().map_err(|e| {
-        CommonError::extend(&CommonDefaultErrorKind::Critical, "", e)
-    })?;
-    crate::interpreter::lua::LuaInterpreterInitialization::shutdown().map_err(|e| {
-        CommonError::extend(&CommonDefaultErrorKind::Critical, "", e)
-    })?;
+    if PY_INITIALIZED.swap(false, Ordering::SeqCst) {
+        crate::interpreter::py::PyInterpreterInitialization::shutdown().map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::Critical, "", e)
+        })?;
+    }
+    if LUA_INITIALIZED.swap(false, Ordering::SeqCst) {
+        crate::interpreter::lua::LuaInterpreterInitialization::shutdown().map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::Critical, "", e)
+        })?;
+    }
     Ok(())
 }
 pub fn interpreter_init(global : &'static dyn GlobalLayout) -> Result<(),CommonError> {
     crate::global::GLOBAL_REFER.get_or_init(|| global);
-    
-    crate::interpreter::py::PyInterpreterInitialization::init().map_err(|e| {
-        CommonError::extend(&CommonDefaultErrorKind::Critical, "", e)
-    })?;
-    crate::interpreter::lua::LuaInterpreterInitialization::init().map_err(|e| {
-        CommonError::extend(&CommonDefaultErrorKind::Critical, "", e)
-    })?;
     Ok(())
-}
\ No newline at end of file
+}
+
+// initializes one interpreter language runtime once, callers must serialize calls for the same language
+pub fn interpreter_lang_init(lang : &'_ str, pool_size : usize) -> Result<(),CommonError> {
+    if crate::global::GLOBAL_REFER.get().is_none() {
+        return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "interpreter not initialized").to_result();
+    }
+
+    match lang {
+        "python" => {
+            if PY_INITIALIZED.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            crate::interpreter::py::PyInterpreterInitialization::init(pool_size).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::Critical, "", e)
+            })?;
+            PY_INITIALIZED.store(true, Ordering::SeqCst);
+        },
+        "lua" => {
+            if LUA_INITIALIZED.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            crate::interpreter::lua::LuaInterpreterInitialization::init(pool_size).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::Critical, "", e)
+            })?;
+            LUA_INITIALIZED.store(true, Ordering::SeqCst);
+        },
+        _ => {
+            return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support interpreter {}", lang)).to_result();
+        }
+    }
+    Ok(())
+}
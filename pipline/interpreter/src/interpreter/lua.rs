@@ -1,11 +1,12 @@
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use mlua::prelude::{Lua, LuaResult, LuaTable};
 
 use common_rs::c_err::CommonError;
 use common_rs::c_err::gen::CommonDefaultErrorKind;
 use common_rs::exec::interfaces::pair::PairValueEnum;
-use mlua::{AnyUserData, Error, Table, UserData, Value};
+use mlua::{AnyUserData, Error, Function, Table, UserData, Value};
 use mypip_types::interface::GlobalLayout;
 
 macro_rules! make_lua_error_message {
@@ -82,14 +83,60 @@ impl<'a> crate::utils::ConvertInterpreterParam<Value> for LuaScriptConverter<'a>
 
 struct LuaPairConverter;
 
+impl LuaPairConverter {
+    fn convert_value(&self, v : &'_ Value) -> Result<PairValueEnum, CommonError> {
+        let data = match v {
+            Value::Nil => PairValueEnum::Null,
+            Value::LightUserData(ud) if ud.0.is_null() => PairValueEnum::Null,
+            Value::Boolean(b) => PairValueEnum::Bool(*b),
+            Value::Integer(i) => PairValueEnum::BigInt(*i),
+            Value::Number(n) => PairValueEnum::Double(*n),
+            Value::String(s) => PairValueEnum::String(s.to_string_lossy().to_string()),
+            // an empty table has no keys to tell a map from an array, so it becomes an empty array
+            Value::Table(t) if t.raw_len() > 0 || t.pairs::<Value, Value>().next().is_none() => {
+                let mut arr = Vec::with_capacity(t.raw_len());
+                for idx in 1..=t.raw_len() {
+                    let ele = t.raw_get::<Value>(idx).map_err(|e| {
+                        CommonError::new(&CommonDefaultErrorKind::Etc, format!("convert lua -> pair array failed :{}", e))
+                    })?;
+                    arr.push(self.convert_value(&ele).map_err(|e| {
+                        CommonError::extend(&CommonDefaultErrorKind::Etc, "nested failed", e)
+                    })?);
+                }
+                PairValueEnum::Array(arr)
+            },
+            Value::Table(t) => {
+                let mut map = std::collections::HashMap::new();
+                for pair in t.pairs::<Value, Value>() {
+                    let (k, v) = pair.map_err(|e| {
+                        CommonError::new(&CommonDefaultErrorKind::Etc, format!("convert lua -> pair map failed :{}", e))
+                    })?;
+                    let key = match k {
+                        Value::String(s) => s.to_string_lossy().to_string(),
+                        Value::Integer(i) => i.to_string(),
+                        _ => return CommonError::new(&CommonDefaultErrorKind::Etc, "convert lua -> pair map key failed").to_result()
+                    };
+                    map.insert(key, self.convert_value(&v).map_err(|e| {
+                        CommonError::extend(&CommonDefaultErrorKind::Etc, "nested failed", e)
+                    })?);
+                }
+                PairValueEnum::Map(map)
+            },
+            _ => return CommonError::new(&CommonDefaultErrorKind::Etc, "convert lua -> pair failed").to_result()
+        };
+        Ok(data)
+    }
+}
+
 impl crate::utils::ConvertPairValue<Table> for LuaPairConverter {
     fn convert(&self, param: &'_ Table) -> Result<PairValueEnum, CommonError> {
-        let mut real_args = Vec::with_capacity(5);
-        for pair in param.sequence_values::<Value>() {
-            let data = match pair.as_ref().map_err(|_|  {
+        let mut real_args = Vec::with_capacity(param.raw_len());
+        for idx in 1..=param.raw_len() {
+            let data = match param.raw_get::<Value>(idx).as_ref().map_err(|_|  {
                 CommonError::new(&CommonDefaultErrorKind::Etc, "convert lua -> pair match failed")
             })? {
                 Value::Nil => PairValueEnum::Null,
+                Value::LightUserData(ud) if ud.0.is_null() => PairValueEnum::Null,
                 Value::Boolean(b) => PairValueEnum::Bool(*b),
                 Value::Integer(i) => PairValueEnum::BigInt(*i),
                 Value::Number(n) => PairValueEnum::Double(*n),
@@ -128,12 +175,32 @@ fn lua_exec_pair_conn_wrapper(vm : &Lua, (conn_name, cmd, args) : (String, Strin
     }
 }
 
+// pool_size caps the live lua vms, like max_workers for python
+static LUA_MAX_VM : AtomicUsize = AtomicUsize::new(0);
+static LUA_VM_CNT : AtomicUsize = AtomicUsize::new(0);
+
 pub(crate) struct LuaInterpreterInitialization;
 impl LuaInterpreterInitialization {
-    pub(crate) fn init() -> Result<(), CommonError> {
+    pub(crate) fn init(pool_size : usize) -> Result<(), CommonError> {
+        if pool_size == 0 {
+            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, "lua pool_size must be greater than 0").to_result();
+        }
+        LUA_MAX_VM.store(pool_size, Ordering::SeqCst);
         Ok(())
     }
     pub(crate) fn shutdown() -> Result<(), CommonError> {
+        LUA_MAX_VM.store(0, Ordering::SeqCst);
+        Ok(())
+    }
+    fn acquire_vm() -> Result<(), CommonError> {
+        let max = LUA_MAX_VM.load(Ordering::SeqCst);
+        if max == 0 {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, "lua interpreter not initialized").to_result();
+        }
+        if LUA_VM_CNT.fetch_add(1, Ordering::SeqCst) >= max {
+            LUA_VM_CNT.fetch_sub(1, Ordering::SeqCst);
+            return CommonError::new(&CommonDefaultErrorKind::NoData, format!("lua vm limit {} reached", max)).to_result();
+        }
         Ok(())
     }
 
@@ -193,6 +260,7 @@ impl LuaInterpreter {
             })?;
         }
 
+        LuaInterpreterInitialization::acquire_vm()?;
         Ok(LuaInterpreter {
             lua: lua_vm,
             global_ref : g_ref
@@ -200,6 +268,12 @@ impl LuaInterpreter {
     }
 }
 
+impl Drop for LuaInterpreter {
+    fn drop(&mut self) {
+        LUA_VM_CNT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 impl crate::Interpreter for LuaInterpreter {
     fn gc(&self) -> Result<(), CommonError> {
         self.lua.gc_collect().map_err(|_| {
@@ -215,4 +289,133 @@ impl crate::Interpreter for LuaInterpreter {
             CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("execute failed {}, {}", name, e.to_string()))
         })
     }
-}
\ No newline at end of file
+
+    fn transform(&self, name : &'_ str, func : &'_ str, data : &'_ PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        use crate::utils::ConvertInterpreterParam;
+
+        let script = self.get_script(name)?;
+        self.lua.load(script).exec().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("execute failed {}, {}", name, e.to_string()))
+        })?;
+
+        let f : Function = self.lua.globals().get(func).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists function {}:{}, {}", name, func, e.to_string()))
+        })?;
+
+        let arg = LuaScriptConverter { vm : &self.lua }.convert(data).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::Etc, "script converter failed", e)
+        })?;
+
+        let ret : Value = f.call(arg).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("call failed {}:{}, {}", name, func, e.to_string()))
+        })?;
+
+        LuaPairConverter.convert_value(&ret).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::Etc, "pair converter failed", e)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use common_rs::exec::interfaces::pair::PairValueEnum;
+    use mlua::Lua;
+    use crate::utils::{ConvertInterpreterParam, ConvertPairValue};
+    use super::{LuaPairConverter, LuaScriptConverter};
+
+    fn get_col<'a>(data : &'a PairValueEnum, col : &'_ str) -> &'a Vec<PairValueEnum> {
+        match data {
+            PairValueEnum::Map(m) => match m.get(col) {
+                Some(PairValueEnum::Array(arr)) => arr,
+                other => panic!("{} not array {:?}", col, other)
+            },
+            other => panic!("not map {:?}", other)
+        }
+    }
+
+    #[test]
+    fn null_round_trip() {
+        let lua = Lua::new();
+        let data = PairValueEnum::Map(HashMap::from([
+            (String::from("name"), PairValueEnum::Array(vec![
+                PairValueEnum::String(String::from("a")),
+                PairValueEnum::Null,
+                PairValueEnum::String(String::from("c")),
+                PairValueEnum::Null,
+            ])),
+            (String::from("score"), PairValueEnum::Array(vec![
+                PairValueEnum::Null,
+                PairValueEnum::Double(1.5),
+                PairValueEnum::Bool(true),
+            ])),
+        ]));
+
+        let v = LuaScriptConverter { vm : &lua }.convert(&data).unwrap();
+        let ret = LuaPairConverter.convert_value(&v).unwrap();
+
+        let name = get_col(&ret, "name");
+        assert_eq!(name.len(), 4);
+        assert!(matches!(&name[0], PairValueEnum::String(s) if s == "a"));
+        assert!(matches!(name[1], PairValueEnum::Null));
+        assert!(matches!(&name[2], PairValueEnum::String(s) if s == "c"));
+        assert!(matches!(name[3], PairValueEnum::Null));
+
+        let score = get_col(&ret, "score");
+        assert_eq!(score.len(), 3);
+        assert!(matches!(score[0], PairValueEnum::Null));
+        assert!(matches!(score[1], PairValueEnum::Double(d) if d == 1.5));
+        assert!(matches!(score[2], PairValueEnum::Bool(true)));
+    }
+
+    #[test]
+    fn transform_returns_input_with_null() {
+        let lua = Lua::new();
+        let data = PairValueEnum::Map(HashMap::from([
+            (String::from("id"), PairValueEnum::Array(vec![PairValueEnum::Null, PairValueEnum::Double(2.0)])),
+        ]));
+        let f : mlua::Function = lua.load("return function(x) return x end").eval().unwrap();
+
+        let arg = LuaScriptConverter { vm : &lua }.convert(&data).unwrap();
+        let ret = LuaPairConverter.convert_value(&f.call(arg).unwrap()).unwrap();
+
+        let id = get_col(&ret, "id");
+        assert_eq!(id.len(), 2);
+        assert!(matches!(id[0], PairValueEnum::Null));
+        assert!(matches!(id[1], PairValueEnum::Double(d) if d == 2.0));
+    }
+
+    #[test]
+    fn args_keep_null_position() {
+        let lua = Lua::new();
+        let args = lua.create_table().unwrap();
+        args.raw_set(1, 1).unwrap();
+        args.raw_set(2, mlua::Value::NULL).unwrap();
+        args.raw_set(3, "x").unwrap();
+
+        let ret = ConvertPairValue::convert(&LuaPairConverter, &args).unwrap();
+        match ret {
+            PairValueEnum::Array(arr) => {
+                assert_eq!(arr.len(), 3);
+                assert!(matches!(arr[0], PairValueEnum::BigInt(1)));
+                assert!(matches!(arr[1], PairValueEnum::Null));
+                assert!(matches!(&arr[2], PairValueEnum::String(s) if s == "x"));
+            },
+            other => panic!("not array {:?}", other)
+        }
+    }
+
+    #[test]
+    fn empty_table_is_empty_array() {
+        let lua = Lua::new();
+        let f : mlua::Function = lua.load("return function() return {} end").eval().unwrap();
+
+        let ret = LuaPairConverter.convert_value(&f.call(()).unwrap()).unwrap();
+        assert!(matches!(&ret, PairValueEnum::Array(arr) if arr.is_empty()));
+
+        let data = PairValueEnum::Map(HashMap::from([(String::from("id"), PairValueEnum::Array(Vec::new()))]));
+        let v = LuaScriptConverter { vm : &lua }.convert(&data).unwrap();
+        let ret = LuaPairConverter.convert_value(&v).unwrap();
+        assert!(get_col(&ret, "id").is_empty());
+    }
+}
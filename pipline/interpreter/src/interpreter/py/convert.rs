@@ -1,9 +1,10 @@
+use std::collections::HashMap;
 use common_rs::c_err::CommonError;
 use common_rs::c_err::gen::CommonDefaultErrorKind;
 use common_rs::exec::interfaces::pair::PairValueEnum;
 
 use pyo3::{Bound, BoundObject, Py, PyAny, Python};
-use pyo3::prelude::{PyAnyMethods, PyListMethods, PyTypeMethods};
+use pyo3::prelude::{PyAnyMethods, PyDictMethods, PyListMethods, PyTypeMethods};
 use pyo3::types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyNone, PyString, PyType};
 
 pub(super) struct PyPariConverter<'py> {
@@ -21,6 +22,82 @@ impl<'py> PyPariConverter<'py> {
     }
 }
 
+impl<'py> PyPariConverter<'py> {
+    pub(super) fn convert_value(&self, ele : &'_ Bound<'_, PyAny>) -> Result<PairValueEnum, CommonError> {
+        let type_name = Self::get_element_type(ele).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::Etc, "get_element_type failed", e)
+        })?;
+
+        let data = match type_name.as_str() {
+            "str" => {
+                let s : String = ele.extract::<String>().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })?;
+                PairValueEnum::String(s)
+            },
+            "float" => {
+                let f : f64 = ele.extract::<f64>().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })?;
+                PairValueEnum::Double(f)
+            },
+            "int" => {
+                let i : i64 = ele.extract::<i64>().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })?;
+                PairValueEnum::BigInt(i)
+            },
+            "bool" => {
+                let b : bool = ele.extract::<bool>().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })?;
+                PairValueEnum::Bool(b)
+            },
+            "NoneType" => PairValueEnum::Null,
+            "bytes" => {
+                let b : Vec<u8> = ele.extract::<Vec<u8>>().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })?;
+                PairValueEnum::Bin(b)
+            },
+            "list" | "tuple" => {
+                let mut v = Vec::new();
+                for sub in ele.try_iter().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })? {
+                    let sub = sub.map_err(|e| {
+                        CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                    })?;
+                    v.push(self.convert_value(&sub).map_err(|e| {
+                        CommonError::extend(&CommonDefaultErrorKind::Etc, "nested failed", e)
+                    })?);
+                }
+                PairValueEnum::Array(v)
+            },
+            "dict" => {
+                let dict : &Bound<PyDict> = ele.cast().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })?;
+                let mut m = HashMap::with_capacity(dict.len());
+                for (k, v) in dict.iter() {
+                    let key = k.str().map_err(|e| {
+                        CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                    })?.to_string();
+                    m.insert(key, self.convert_value(&v).map_err(|e| {
+                        CommonError::extend(&CommonDefaultErrorKind::Etc, "nested failed", e)
+                    })?);
+                }
+                PairValueEnum::Map(m)
+            },
+            _ => {
+                return CommonError::new(&CommonDefaultErrorKind::NoSupport,format!("not convert type {}", type_name.as_str())).to_result();
+            }
+        };
+
+        Ok(data)
+    }
+}
+
 impl<'a> crate::utils::ConvertPairValue<Bound<'a, PyAny>> for PyPariConverter<'a> {
 
 
@@ -32,40 +109,7 @@ impl<'a> crate::utils::ConvertPairValue<Bound<'a, PyAny>> for PyPariConverter<'a
         let mut v = Vec::with_capacity(list.len());
 
         for ele in list.iter() {
-            let type_name = Self::get_element_type(&ele).map_err(|e| {
-                CommonError::extend(&CommonDefaultErrorKind::Etc, "get_element_type failed", e)
-            })?;
-
-            let data = match type_name.as_str() {
-                "str" => {
-                    let s : String = ele.extract::<String>().map_err(|e| {
-                        CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
-                    })?;
-                    PairValueEnum::String(s)
-                },
-                "float" => {
-                    let f : f64 = ele.extract::<f64>().map_err(|e| {
-                        CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
-                    })?;
-                    PairValueEnum::Double(f)
-                },
-                "int" => {
-                    let i : i64 = ele.extract::<i64>().map_err(|e| {
-                        CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
-                    })?;
-                    PairValueEnum::BigInt(i)
-                },
-                "bool" => {
-                    let b : bool = ele.extract::<bool>().map_err(|e| {
-                        CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
-                    })?;
-                    PairValueEnum::Bool(b)
-                },
-                _ => {
-                    return CommonError::new(&CommonDefaultErrorKind::NoSupport,format!("not convert type {}", type_name.as_str())).to_result();
-                }
-            };
-            v.push(data);
+            v.push(self.convert_value(&ele)?);
         }
 
         Ok(PairValueEnum::Array(v))
@@ -5,6 +5,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{LazyLock, Mutex, OnceLock};
 use common_rs::c_err::CommonError;
 use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
 use pyo3::ffi::c_str;
 use pyo3::prelude::{PyAnyMethods, PyBoolMethods, PyDictMethods, PyListMethods, PyModule, PyModuleMethods};
 use pyo3::{pyfunction, pymodule, wrap_pyfunction, Bound, CastError, Py, PyAny, PyErr, PyResult, Python};
@@ -21,7 +22,7 @@ import sys
 
 sys.stdout.reconfigure(line_buffering=True)
 te_map = {}
-te = ThreadPoolExecutor(max_workers=100)
+te = ThreadPoolExecutor(max_workers=__MAX_WORKERS__)
 global_map = {}
 
 def __internal_run_eval(code):
@@ -64,7 +65,7 @@ fn py_exec_pair_conn_wrapper(py: Python, conn_name : String, cmd : String, args
 pub(crate) struct PyInterpreterInitialization;
 
 impl PyInterpreterInitialization {
-    pub(crate) fn init() -> Result<(), CommonError> {
+    pub(crate) fn init(max_workers : usize) -> Result<(), CommonError> {
         Python::initialize();
         Python::attach(|py| {
             let g = crate::global::GLOBAL_REFER.get().expect("global refer get is broken");
@@ -98,7 +99,7 @@ impl PyInterpreterInitialization {
                 }
             }
 
-            let cstr = CString::new(PY_INIT_CODE);
+            let cstr = CString::new(PY_INIT_CODE.replace("__MAX_WORKERS__", max_workers.to_string().as_str()));
 
             if cstr.is_err() {
                 let panic_err = CommonError::new(&CommonDefaultErrorKind::Critical, cstr.err().unwrap().to_string());
@@ -344,4 +345,50 @@ impl crate::Interpreter for PyInterpreter {
 
         Ok(())
     }
+
+    fn transform(&self, name : &'_ str, func : &'_ str, data : &'_ PairValueEnum) -> Result<PairValueEnum, CommonError> {
+        use crate::utils::ConvertInterpreterParam;
+
+        let script = self.get_script(name)?;
+        let cstr = CString::new(script).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?;
+
+        let mut attach_ret : Result<PairValueEnum, CommonError> = Ok(PairValueEnum::Null);
+
+        Python::attach(|py| {
+            attach_ret = (|| {
+                let main_package = py.import("__main__").map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })?;
+                let globals = main_package.dict().copy().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })?;
+
+                py.run(cstr.as_c_str(), Some(&globals), None).map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("execute failed {}, {}", name, e))
+                })?;
+
+                let f = globals.get_item(func).map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ThirdLibCallFail, e.to_string())
+                })?.ok_or_else(|| {
+                    CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists function {}:{}", name, func))
+                })?;
+
+                let arg = super::convert::PyScriptConverter { py }.convert(data).map_err(|e| {
+                    CommonError::extend(&CommonDefaultErrorKind::Etc, "script converter failed", e)
+                })?;
+
+                let ret = f.call1((arg,)).map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("call failed {}:{}, {}", name, func, e))
+                })?;
+
+                super::convert::PyPariConverter { py }.convert_value(&ret).map_err(|e| {
+                    CommonError::extend(&CommonDefaultErrorKind::Etc, "pair converter failed", e)
+                })
+            })();
+        });
+
+        attach_ret
+    }
 }
\ No newline at end of file
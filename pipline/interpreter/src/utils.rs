@@ -22,6 +22,20 @@ pub(crate) fn exec_pair_conn<T, R>(global : &'static dyn GlobalLayout,
        CommonError::extend(&CommonDefaultErrorKind::Etc, "pair converter failed", e)
     })?;
 
+    let need_check = global.acquire_conn_slot(conn_name.into()).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::Etc, "acquire connection failed", e)
+    })?;
+    let conn_ret = exec_pair_query(global, conn_name, query, &real_args, need_check);
+    global.release_conn_slot(conn_name.into())?;
+
+    let conn_data = conn_ret?;
+
+    script_converter.convert(&conn_data).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::Etc, "script converter failed", e)
+    })
+}
+
+fn exec_pair_query(global : &'static dyn GlobalLayout, conn_name : &'_ str, query : &'_ str, real_args : &'_ PairValueEnum, need_check : bool) -> Result<PairValueEnum, CommonError> {
     let pool_get_ret = unsafe {
         global.get_exec_pool(conn_name.into())
     }.map_err(|e| {
@@ -32,20 +46,23 @@ pub(crate) fn exec_pair_conn<T, R>(global : &'static dyn GlobalLayout,
         CommonError::extend(&CommonDefaultErrorKind::Etc, "get exec pool item failed", e)
     })?;
 
+    if need_check && item.get_value().get_current_time().is_err() {
+        item.dispose();
+        item = pool_get_ret.get_owned(()).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::Etc, "get exec pool item failed", e)
+        })?;
+    }
+
     let conn =item.get_value();
 
-    let conn_ret = conn.execute_pair(query, &real_args);
+    let conn_ret = conn.execute_pair(query, real_args);
 
-    let conn_data = if conn_ret.is_err() {
+    if conn_ret.is_err() {
         item.dispose();
-        return CommonError::extend(&CommonDefaultErrorKind::ExecuteFail,
-                                   "execute failed", conn_ret.err().unwrap()).to_result();
+        CommonError::extend(&CommonDefaultErrorKind::ExecuteFail,
+                                   "execute failed", conn_ret.err().unwrap()).to_result()
     } else {
         item.restoration();
         Ok(conn_ret.unwrap())
-    }?;
-
-    script_converter.convert(&conn_data).map_err(|e| {
-        CommonError::extend(&CommonDefaultErrorKind::Etc, "script converter failed", e)
-    })
+    }
 }
\ No newline at end of file
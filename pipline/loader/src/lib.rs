@@ -1,3 +1,5 @@
 pub mod toml_file_loader;
 pub mod pair_db_loader;
+pub mod tls;
+pub mod secret;
 
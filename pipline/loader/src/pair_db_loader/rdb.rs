@@ -7,11 +7,13 @@ use common_rs::c_err::gen::CommonDefaultErrorKind;
 use common_rs::exec::duckdb::create_duckdb_pair_conn_pool;
 use common_rs::exec::interfaces::pair::{PairExecutorInfo, PairExecutorPool, PairValueEnum};
 use common_rs::exec::pg::create_pg_pair_conn_pool;
+use mypip_exec::sqlite::create_sqlite_pair_conn_pool;
 use mypip_types::config::app::AppConfig;
-use mypip_types::config::conn::{ConnectionInfo, ConnectionInfos, OdbcConnectionInfo};
-use mypip_types::config::plan::{Plan, PlanChain, PlanChainArgs, PlanChainBindParam, PlanInterval, PlanRoot, PlanScript};
+use mypip_types::config::conn::{ConnectionHealthInfo, ConnectionInfo, ConnectionInfos, ConnectionTlsInfo, ConnectionTopologyInfo, OdbcConnectionInfo};
+use mypip_types::config::plan::{plan_watermark_store_default, Plan, PlanAssert, PlanAssertFreshness, PlanAssertRange, PlanChain, PlanChainArgs, PlanChainBindParam, PlanDeadLetter, PlanInterval, PlanRoot, PlanPaginate, PlanScript, PlanSink, PlanWatermark};
 use mypip_types::interface::ConfLoader;
 use crate::pair_db_loader::utils;
+use crate::secret::{resolve_secret, SecretKey, SECRET_PREFIX};
 
 macro_rules! get_pair_db_connection {
     ($obj:expr) => {
@@ -27,16 +29,20 @@ pub struct PairDbLoader {
     identifier : String,
     db_pool : PairExecutorPool,
     is_toml : bool,
+    is_sqlite : bool,
     plan_query : &'static str,
     conn_query : &'static str,
     script_query : &'static str,
+    watermark_query : (&'static str, &'static str),
+    conn_passwd_query : (&'static str, &'static str),
 
     once_init_flag : bool,
+    secret_key : Option<SecretKey>,
     once_cache : (OnceLock<PlanRoot>, OnceLock<ConnectionInfos>,  OnceLock<HashMap<String,String>>)
 }
 
 impl PairDbLoader {
-    pub fn new(identifier : String ,conf_path : &'_ str, load_once : bool, is_toml : bool) -> Result<Self, CommonError> {
+    pub fn new(identifier : String ,conf_path : &'_ str, load_once : bool, is_toml : bool, secret_key : Option<SecretKey>) -> Result<Self, CommonError> {
         let mut app_path = PathBuf::from_str(conf_path).map_err(|e| {
             CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
         })?;
@@ -57,18 +63,22 @@ impl PairDbLoader {
         }
 
         let db_conf = convert.db_config.as_ref().expect("db_config is broken");
+        crate::tls::check_tls_applicable("db_config", db_conf.db_type.as_str(), db_conf.tls.as_ref())?;
         let conn_info = PairExecutorInfo {
             addr: vec![db_conf.db_address.clone()],
             name: db_conf.db_name.clone(),
             user: db_conf.db_user.clone(),
-            password: db_conf.db_password.clone(),
+            password: resolve_secret(secret_key.as_ref(), db_conf.db_password.as_str()).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, "db_config db_password decrypt failed", e)
+            })?,
             timeout_sec: 10,
-            extend : None
+            extend : crate::tls::create_tls_extend(db_conf.tls.as_ref())
         };
 
         let p = match db_conf.db_type.as_str() {
             "postgres" => create_pg_pair_conn_pool("pair_db_loader".to_string(), conn_info, 1),
             "duckdb" => create_duckdb_pair_conn_pool("pair_db_loader".to_string(),conn_info, 1),
+            "sqlite" => create_sqlite_pair_conn_pool("pair_db_loader".to_string(),conn_info, 1),
             _ => {
                 return CommonError::new(&CommonDefaultErrorKind::NoSupport,
                                         format!("not support {}", db_conf.db_type)).to_result();
@@ -77,25 +87,100 @@ impl PairDbLoader {
 
         let (plan_query, conn_query, script_query) = match db_conf.db_type.as_str() {
             "postgres" => (if is_toml {utils::plan_toml_select_query!("$1")} else {utils::plan_select_query!("$1")}, utils::conn_select_query!("$1"), utils::script_data_select_query!("$1")),
-            "duckdb" => (if is_toml {utils::plan_toml_select_query!("?")} else {utils::plan_select_query!("?")}, utils::conn_select_query!("?"), utils::script_data_select_query!("?")),
+            "duckdb" | "sqlite" => (if is_toml {utils::plan_toml_select_query!("?")} else {utils::plan_select_query!("?")}, utils::conn_select_query!("?"), utils::script_data_select_query!("?")),
             _ => {
                 return CommonError::new(&CommonDefaultErrorKind::NoSupport,
                                         format!("not support {}", db_conf.db_type)).to_result();
             }
         };
 
+        let watermark_query = match db_conf.db_type.as_str() {
+            "postgres" => (utils::watermark_select_query!("$1", "$2"), utils::watermark_upsert_query!("$1", "$2", "$3")),
+            _ => (utils::watermark_select_query!("?", "?"), utils::watermark_upsert_query!("?", "?", "?")),
+        };
+        let conn_passwd_query = match db_conf.db_type.as_str() {
+            "postgres" => (utils::conn_passwd_select_query!("$1"), utils::conn_passwd_update_query!("$1", "$2", "$3")),
+            _ => (utils::conn_passwd_select_query!("?"), utils::conn_passwd_update_query!("?", "?", "?")),
+        };
+
         Ok(PairDbLoader {
             identifier,
             db_pool : p,
             once_init_flag : load_once,
+            secret_key,
             is_toml,
+            is_sqlite : db_conf.db_type == "sqlite",
             plan_query,
             conn_query,
             script_query,
+            watermark_query,
+            conn_passwd_query,
             once_cache : (OnceLock::new(), OnceLock::new(), OnceLock::new()),
         })
     }
 
+    pub fn rotate_conn_passwd(&self, rotate : &'_ dyn Fn(&str) -> Result<String, CommonError>) -> Result<usize, CommonError> {
+        let mut item = get_pair_db_connection!(self)?;
+        let conn = item.get_value();
+
+        let data = conn.execute_pair(self.conn_passwd_query.0, &PairValueEnum::Array(vec![PairValueEnum::String(self.identifier.clone())])).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "", e)
+        })?;
+        let name = utils::get_col_ref!("name", &data, str)?;
+        let conn_passwd = utils::get_col_ref!("conn_passwd", &data, str)?;
+
+        let mut rows = Vec::with_capacity(name.len());
+        for (n, passwd) in name.iter().zip(conn_passwd.iter()).filter(|(_, x)| x.starts_with(SECRET_PREFIX)) {
+            let value = rotate(passwd.as_str()).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("rotate conn_passwd {} failed", n), e)
+            })?;
+            rows.push(vec![PairValueEnum::String(value), PairValueEnum::String(self.identifier.clone()), PairValueEnum::String((*n).clone())]);
+        }
+
+        let cnt = rows.len();
+        let empty = PairValueEnum::Array(Vec::new());
+        conn.execute_pair("BEGIN", &empty).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "rotate conn_passwd begin failed", e)
+        })?;
+        for row in rows {
+            if let Err(e) = conn.execute_pair(self.conn_passwd_query.1, &PairValueEnum::Array(row)) {
+                let _ = conn.execute_pair("ROLLBACK", &empty);
+                return CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "rotate conn_passwd update failed", e).to_result();
+            }
+        }
+        conn.execute_pair("COMMIT", &empty).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "rotate conn_passwd commit failed", e)
+        })?;
+        Ok(cnt)
+    }
+
+    fn split_col_list(s : &'_ str) -> Vec<String> {
+        s.split(',').map(|col| col.trim().to_string()).filter(|col| !col.is_empty()).collect()
+    }
+
+    fn parse_assert_range(ch_id : &'_ str, s : &'_ str) -> Result<Vec<PlanAssertRange>, CommonError> {
+        let parse_bound = |x : &'_ str| {
+            let x = x.trim();
+            if x.is_empty() {
+                return Ok(None);
+            }
+            x.parse::<f64>().map(Some).map_err(|e| {
+                CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("{} assert_range {} parse failed : {}", ch_id, s, e))
+            })
+        };
+
+        Self::split_col_list(s).iter().map(|item| {
+            match item.split(':').collect::<Vec<&str>>().as_slice() {
+                [column, min, max] if !column.trim().is_empty() => Ok(PlanAssertRange {
+                    column : column.trim().to_string(),
+                    min : parse_bound(min)?,
+                    max : parse_bound(max)?
+                }),
+                _ => CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("{} assert_range {} need column:min:max", ch_id, item)).to_result()
+            }
+        }).collect()
+    }
+
     fn create_plan_data(plan_type : String, interval_conn : Option<String>,
                         interval_sec : u64) -> Result<Plan, CommonError> {
 
@@ -143,6 +228,7 @@ impl PairDbLoader {
                 current_index = idx;
             }
         }
+        ret.push((current_name, current_index, s.len()));
         ret
     }
 
@@ -158,31 +244,88 @@ impl PairDbLoader {
 
         let param = PairValueEnum::Array(vec![PairValueEnum::String(self.identifier.clone())]);
 
-        let data = conn.execute_pair(self.plan_query, &param).map_err(|e| {
+        let mut data = conn.execute_pair(self.plan_query, &param).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "", e)
         })?;
+        if self.is_sqlite {
+            utils::coerce_sqlite_cols(&mut data, &utils::PLAN_SQLITE_I32_COLS, &utils::PLAN_SQLITE_BOOL_COLS)?;
+        }
 
         let plan_name = utils::get_col_ref!("plan_name", &data, str)?;
         let plan_type = utils::get_col_ref!("type", &data, str)?;
         let interval_connection = utils::get_col_ref!("interval_connection", &data, str, null)?;
         let interval_second = utils::get_col_ref!("interval_second", &data, i32)?;
+        let plan_transaction = utils::get_col_ref!("plan_transaction", &data, bool, null)?;
+        let plan_watermark_step = utils::get_col_ref!("plan_watermark_step", &data, str, null)?;
+        let plan_watermark_column = utils::get_col_ref!("plan_watermark_column", &data, str, null)?;
+        let plan_watermark_store = utils::get_col_ref!("plan_watermark_store", &data, str, null)?;
+        let plan_watermark_path = utils::get_col_ref!("plan_watermark_path", &data, str, null)?;
+        let plan_watermark_initial = utils::get_col_ref!("plan_watermark_initial", &data, str, null)?;
         let chain_id = utils::get_col_ref!("chain_id", &data, str, null)?;
         let chain_connection = utils::get_col_ref!("chain_connection", &data, str, null)?;
         let chain_query = utils::get_col_ref!("chain_query", &data, str, null)?;
+        let chain_when = utils::get_col_ref!("chain_when", &data, str, null)?;
+        let chain_mode = utils::get_col_ref!("chain_mode", &data, str, null)?;
+        let chain_batch_size = utils::get_col_ref!("chain_batch_size", &data, i32, null)?;
+        let chain_source_row_col = utils::get_col_ref!("chain_source_row_col", &data, str, null)?;
+        let chain_tx_group = utils::get_col_ref!("chain_tx_group", &data, str, null)?;
+        let chain_kind = utils::get_col_ref!("chain_kind", &data, str, null)?;
+        let chain_script_lang = utils::get_col_ref!("chain_script_lang", &data, str, null)?;
+        let chain_script_file = utils::get_col_ref!("chain_script_file", &data, str, null)?;
+        let chain_script_func = utils::get_col_ref!("chain_script_func", &data, str, null)?;
+        let chain_after_ids = utils::get_col_ref!("chain_after_ids", &data, str, null)?;
+        let chain_bind_mode = utils::get_col_ref!("chain_bind_mode", &data, str, null)?;
+        let chain_lookup_key = utils::get_col_ref!("chain_lookup_key", &data, str, null)?;
+        let chain_sink_step = utils::get_col_ref!("chain_sink_step", &data, str, null)?;
+        let chain_sink_format = utils::get_col_ref!("chain_sink_format", &data, str, null)?;
+        let chain_sink_path = utils::get_col_ref!("chain_sink_path", &data, str, null)?;
+        let chain_sink_columns = utils::get_col_ref!("chain_sink_columns", &data, str, null)?;
+        let chain_sink_gzip = utils::get_col_ref!("chain_sink_gzip", &data, bool, null)?;
+        let chain_sink_rotate_rows = utils::get_col_ref!("chain_sink_rotate_rows", &data, i32, null)?;
+        let chain_assert_step = utils::get_col_ref!("chain_assert_step", &data, str, null)?;
+        let chain_assert_min_rows = utils::get_col_ref!("chain_assert_min_rows", &data, i32, null)?;
+        let chain_assert_max_rows = utils::get_col_ref!("chain_assert_max_rows", &data, i32, null)?;
+        let chain_assert_not_null = utils::get_col_ref!("chain_assert_not_null", &data, str, null)?;
+        let chain_assert_unique = utils::get_col_ref!("chain_assert_unique", &data, str, null)?;
+        let chain_assert_range = utils::get_col_ref!("chain_assert_range", &data, str, null)?;
+        let chain_assert_freshness_column = utils::get_col_ref!("chain_assert_freshness_column", &data, str, null)?;
+        let chain_assert_freshness_sec = utils::get_col_ref!("chain_assert_freshness_sec", &data, i32, null)?;
+        let chain_paginate_mode = utils::get_col_ref!("chain_paginate_mode", &data, str, null)?;
+        let chain_page_size = utils::get_col_ref!("chain_page_size", &data, i32, null)?;
+        let chain_paginate_column = utils::get_col_ref!("chain_paginate_column", &data, str, null)?;
+        let chain_paginate_initial = utils::get_col_ref!("chain_paginate_initial", &data, str, null)?;
+        let chain_paginate_type = utils::get_col_ref!("chain_paginate_type", &data, str, null)?;
+        let chain_redis_rule = utils::get_col_ref!("chain_redis_rule", &data, str, null)?;
+        let chain_on_row_error = utils::get_col_ref!("chain_on_row_error", &data, str, null)?;
+        let chain_dead_letter_connection = utils::get_col_ref!("chain_dead_letter_connection", &data, str, null)?;
+        let chain_dead_letter_table = utils::get_col_ref!("chain_dead_letter_table", &data, str, null)?;
+        let chain_dead_letter_path = utils::get_col_ref!("chain_dead_letter_path", &data, str, null)?;
+        let chain_read_only = utils::get_col_ref!("chain_read_only", &data, bool, null)?;
         let mapping_type = utils::get_col_ref!("mapping_type", &data, str, null)?;
         let arg_data = utils::get_col_ref!("arg_data", &data, str, null)?;
         let arg_idx = utils::get_col_ref!("arg_idx", &data, i32, null)?;
+        let arg_name = utils::get_col_ref!("arg_name", &data, str, null)?;
+        let arg_type = utils::get_col_ref!("arg_type", &data, str, null)?;
         let bind_id = utils::get_col_ref!("bind_id", &data, str, null)?;
         let bind_key = utils::get_col_ref!("bind_key", &data, str, null)?;
         let bind_row = utils::get_col_ref!("bind_row", &data, i32, null)?;
         let bind_idx = utils::get_col_ref!("bind_idx", &data, i32, null)?;
+        let bind_name = utils::get_col_ref!("bind_name", &data, str, null)?;
+        let bind_cast = utils::get_col_ref!("bind_cast", &data, str, null)?;
         let script_lang = utils::get_col_ref!("script_lang", &data, str, null)?;
         let script_file = utils::get_col_ref!("script_file", &data, str, null)?;
 
-        if !utils::vec_if_same_len!(plan_name, plan_type, interval_connection, interval_second,
-            chain_connection, chain_query,
-            mapping_type, arg_data, arg_idx, bind_id,
-            bind_key, bind_row, bind_idx, script_lang, script_file) {
+        if !utils::vec_if_same_len!(plan_name, plan_type, interval_connection, interval_second, plan_transaction,
+            plan_watermark_step, plan_watermark_column, plan_watermark_store, plan_watermark_path, plan_watermark_initial,
+            chain_connection, chain_query, chain_when, chain_mode, chain_batch_size, chain_source_row_col, chain_tx_group,
+            chain_kind, chain_script_lang, chain_script_file, chain_script_func, chain_after_ids, chain_bind_mode, chain_lookup_key,
+            chain_sink_step, chain_sink_format, chain_sink_path, chain_sink_columns, chain_sink_gzip, chain_sink_rotate_rows,
+            chain_assert_step, chain_assert_min_rows, chain_assert_max_rows, chain_assert_not_null, chain_assert_unique,
+            chain_assert_range, chain_assert_freshness_column, chain_assert_freshness_sec,
+            chain_paginate_mode, chain_page_size, chain_paginate_column, chain_paginate_initial, chain_paginate_type, chain_redis_rule,
+            chain_on_row_error, chain_dead_letter_connection, chain_dead_letter_table, chain_dead_letter_path, chain_read_only,
+            mapping_type, arg_data, arg_idx, arg_name, arg_type, bind_id,
+            bind_key, bind_row, bind_idx, bind_name, bind_cast, script_lang, script_file) {
             return CommonError::new(&CommonDefaultErrorKind::Critical, "").to_result()
         }
 
@@ -197,6 +340,16 @@ impl PairDbLoader {
             ).map_err(|e| {
                 CommonError::extend(&CommonDefaultErrorKind::ParsingFail, "", e)
             })?;
+            p.transaction = plan_transaction[p_range.1].map_or(false, |x| *x);
+            if let (Some(step), Some(column)) = (plan_watermark_step[p_range.1], plan_watermark_column[p_range.1]) {
+                p.watermark = Some(PlanWatermark {
+                    step : step.clone(),
+                    column : column.clone(),
+                    store : plan_watermark_store[p_range.1].map_or_else(plan_watermark_store_default, |x| x.clone()),
+                    path : plan_watermark_path[p_range.1].map(|x| x.clone()),
+                    initial : plan_watermark_initial[p_range.1].map(|x| x.clone()),
+                });
+            }
 
             if plan_type[p_range.1].as_str() == "script" {
                 Self::link_script_to_plan(&mut p,
@@ -223,6 +376,73 @@ impl PairDbLoader {
                             CommonError::new(&CommonDefaultErrorKind::NoData, "").to_result(), |x| { Ok(x) })?.clone();
                         temp_chain.query = chain_query[idx].map_or(
                             CommonError::new(&CommonDefaultErrorKind::NoData, "").to_result(), |x| { Ok(x) })?.clone();
+                        temp_chain.when = chain_when[idx].map(|x| x.clone());
+                        temp_chain.mode = chain_mode[idx].map(|x| x.clone());
+                        temp_chain.batch_size = chain_batch_size[idx].map(|x| *x as usize);
+                        temp_chain.source_row_col = chain_source_row_col[idx].map(|x| x.clone());
+                        temp_chain.tx_group = chain_tx_group[idx].map(|x| x.clone());
+                        temp_chain.kind = chain_kind[idx].map(|x| x.clone());
+                        temp_chain.func = chain_script_func[idx].map(|x| x.clone());
+                        // next_chain_id is only the sort key of the sequential order and holds one value, a join step needs several
+                        temp_chain.after = chain_after_ids[idx].map(|x| {
+                            x.split(|c| c == ',').map(|id| id.trim().to_string()).filter(|id| !id.is_empty()).collect()
+                        });
+                        temp_chain.bind_mode = chain_bind_mode[idx].map(|x| x.clone());
+                        temp_chain.lookup_key = chain_lookup_key[idx].map(|x| {
+                            x.split(|c| c == ',').map(|k| k.trim().to_string()).filter(|k| !k.is_empty()).collect()
+                        });
+                        if let (Some(lang), Some(file)) = (chain_script_lang[idx], chain_script_file[idx]) {
+                            temp_chain.script = Some(PlanScript { lang : lang.clone(), file : file.clone() });
+                        }
+                        if let (Some(step), Some(format), Some(path)) = (chain_sink_step[idx], chain_sink_format[idx], chain_sink_path[idx]) {
+                            temp_chain.sink = Some(PlanSink {
+                                step : step.clone(),
+                                format : format.clone(),
+                                path : path.clone(),
+                                columns : chain_sink_columns[idx].map(|x| {
+                                    x.split(|c| c == ',').map(|col| col.trim().to_string()).filter(|col| !col.is_empty()).collect()
+                                }),
+                                gzip : chain_sink_gzip[idx].map_or(false, |x| *x),
+                                rotate_rows : chain_sink_rotate_rows[idx].map(|x| *x as usize)
+                            });
+                        }
+                        if let Some(step) = chain_assert_step[idx] {
+                            temp_chain.assertion = Some(PlanAssert {
+                                step : step.clone(),
+                                min_rows : chain_assert_min_rows[idx].map(|x| *x as usize),
+                                max_rows : chain_assert_max_rows[idx].map(|x| *x as usize),
+                                not_null : chain_assert_not_null[idx].map(|x| Self::split_col_list(x)),
+                                unique : chain_assert_unique[idx].map(|x| Self::split_col_list(x)),
+                                range : chain_assert_range[idx].map(|x| Self::parse_assert_range(ch_id, x)).transpose()?,
+                                freshness : match (chain_assert_freshness_column[idx], chain_assert_freshness_sec[idx]) {
+                                    (Some(column), Some(sec)) => Some(PlanAssertFreshness { column : column.clone(), max_age_sec : *sec as u64 }),
+                                    _ => None
+                                }
+                            });
+                        }
+                        if let (Some(mode), Some(page_size)) = (chain_paginate_mode[idx], chain_page_size[idx]) {
+                            temp_chain.paginate = Some(PlanPaginate {
+                                mode : mode.clone(),
+                                page_size : *page_size as usize,
+                                column : chain_paginate_column[idx].map(|x| x.clone()),
+                                initial : chain_paginate_initial[idx].map(|x| x.clone()),
+                                type_name : chain_paginate_type[idx].map(|x| x.clone())
+                            });
+                        }
+                        temp_chain.redis = chain_redis_rule[idx].map(|x| {
+                            serde_json::from_str(x.as_str()).map_err(|e| {
+                                CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("{} redis_rule parse failed : {}", ch_id, e))
+                            })
+                        }).transpose()?;
+                        temp_chain.on_row_error = chain_on_row_error[idx].map(|x| x.clone());
+                        if chain_dead_letter_table[idx].is_some() || chain_dead_letter_path[idx].is_some() {
+                            temp_chain.dead_letter = Some(PlanDeadLetter {
+                                connection : chain_dead_letter_connection[idx].map(|x| x.clone()),
+                                table : chain_dead_letter_table[idx].map(|x| x.clone()),
+                                path : chain_dead_letter_path[idx].map(|x| x.clone())
+                            });
+                        }
+                        temp_chain.read_only = chain_read_only[idx].map(|x| *x);
                         current_chain = Some(temp_chain);
                     }
                     let current_c = current_chain.as_mut().expect("get failed current_chain");
@@ -234,21 +454,23 @@ impl PairDbLoader {
                         current_c.args.as_mut().expect("broken current args").push(PlanChainArgs {
                             data: arg_data[idx].map_or(
                                 CommonError::new(&CommonDefaultErrorKind::NoData, "").to_result(), |x| { Ok(x) })?.clone(),
-                            idx : *arg_idx[idx].map_or(
-                                CommonError::new(&CommonDefaultErrorKind::NoData, "").to_result(), |x| { Ok(x) })? as usize,
+                            idx : arg_idx[idx].map_or(0, |x| *x as usize),
+                            name : arg_name[idx].map(|x| x.clone()),
+                            type_name : arg_type[idx].map(|x| x.clone()),
                         });
                     } else {
                         if current_c.bind.is_none() {
                             current_c.bind = Some(Vec::with_capacity(3));
                         }
                         current_c.bind.as_mut().expect("broken current bind").push(PlanChainBindParam {
-                            idx :  *bind_idx[idx].map_or(
-                                CommonError::new(&CommonDefaultErrorKind::NoData, "").to_result(), |x| { Ok(x) })? as usize,
+                            idx : bind_idx[idx].map_or(0, |x| *x as usize),
                             key: bind_key[idx].map_or(
                                 CommonError::new(&CommonDefaultErrorKind::NoData, "").to_result(), |x| { Ok(x) })?.clone(),
                             id: bind_id[idx].map_or(
                                 CommonError::new(&CommonDefaultErrorKind::NoData, "").to_result(), |x| { Ok(x) })?.clone(),
                             row: bind_row[idx].map(|v| {*v as usize}),
+                            name: bind_name[idx].map(|x| x.clone()),
+                            cast: bind_cast[idx].map(|x| x.clone()),
                         });
                     }
                 }
@@ -324,9 +546,12 @@ impl ConfLoader for PairDbLoader {
 
         let param = PairValueEnum::Array(vec![PairValueEnum::String(self.identifier.clone())]);
 
-        let data = conn.execute_pair(self.conn_query, &param).map_err(|e| {
+        let mut data = conn.execute_pair(self.conn_query, &param).map_err(|e| {
             CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "", e)
         })?;
+        if self.is_sqlite {
+            utils::coerce_sqlite_cols(&mut data, &utils::CONN_SQLITE_I32_COLS, &[])?;
+        }
 
         let max_size = utils::get_col_ref!("max_size", &data, i32)?;
         let name = utils::get_col_ref!("name", &data, str)?;
@@ -336,13 +561,35 @@ impl ConfLoader for PairDbLoader {
         let conn_addr = utils::get_col_ref!("conn_addr", &data, str)?;
         let conn_passwd = utils::get_col_ref!("conn_passwd", &data, str)?;
         let conn_timeout = utils::get_col_ref!("conn_timeout", &data, i32)?;
+        let min_idle = utils::get_col_ref!("min_idle", &data, i32, null)?;
+        let idle_timeout_sec = utils::get_col_ref!("idle_timeout_sec", &data, i32, null)?;
+        let max_lifetime_sec = utils::get_col_ref!("max_lifetime_sec", &data, i32, null)?;
+        let acquire_timeout_ms = utils::get_col_ref!("acquire_timeout_ms", &data, i32, null)?;
         let odbc_driver = utils::get_col_ref!("odbc_driver", &data, str, null)?;
         let odbc_current_time_query = utils::get_col_ref!("odbc_current_time_query", &data, str, null)?;
         let odbc_current_time_col_name = utils::get_col_ref!("odbc_current_time_col_name", &data, str, null)?;
+        let odbc_connection_string = utils::get_col_ref!("odbc_connection_string", &data, str, null)?;
+        let odbc_dsn = utils::get_col_ref!("odbc_dsn", &data, str, null)?;
+        let odbc_attributes = utils::get_col_ref!("odbc_attributes", &data, str, null)?;
+        let health_interval_sec = utils::get_col_ref!("health_interval_sec", &data, i32, null)?;
+        let health_failure_threshold = utils::get_col_ref!("health_failure_threshold", &data, i32, null)?;
+        let health_ping_query = utils::get_col_ref!("health_ping_query", &data, str, null)?;
+        let replica_addr = utils::get_col_ref!("replica_addr", &data, str, null)?;
+        let target_session_attrs = utils::get_col_ref!("target_session_attrs", &data, str, null)?;
+        let tls_mode = utils::get_col_ref!("tls_mode", &data, str, null)?;
+        let tls_ca_file = utils::get_col_ref!("tls_ca_file", &data, str, null)?;
+        let tls_cert_file = utils::get_col_ref!("tls_cert_file", &data, str, null)?;
+        let tls_key_file = utils::get_col_ref!("tls_key_file", &data, str, null)?;
+        let tls_server_name = utils::get_col_ref!("tls_server_name", &data, str, null)?;
 
         if !utils::vec_if_same_len!(max_size, name, conn_type, conn_name,
             conn_user, conn_addr,conn_passwd, conn_timeout,
-            odbc_driver, odbc_current_time_query, odbc_current_time_col_name) {
+            min_idle, idle_timeout_sec, max_lifetime_sec, acquire_timeout_ms,
+            odbc_driver, odbc_current_time_query, odbc_current_time_col_name,
+            odbc_connection_string, odbc_dsn, odbc_attributes,
+            health_interval_sec, health_failure_threshold, health_ping_query,
+            replica_addr, target_session_attrs,
+            tls_mode, tls_ca_file, tls_cert_file, tls_key_file, tls_server_name) {
             return CommonError::new(&CommonDefaultErrorKind::Critical, "").to_result()
         }
 
@@ -360,17 +607,57 @@ impl ConfLoader for PairDbLoader {
                 }),
                 conn_passwd: conn_passwd[idx].clone(),
                 conn_timeout: *conn_timeout[idx] as u32,
+                min_idle: min_idle[idx].map(|x| *x as usize),
+                idle_timeout_sec: idle_timeout_sec[idx].map(|x| *x as u64),
+                max_lifetime_sec: max_lifetime_sec[idx].map(|x| *x as u64),
+                acquire_timeout_ms: acquire_timeout_ms[idx].map(|x| *x as u64),
                 odbc: if conn_type[idx] == "odbc" {
                     Some(OdbcConnectionInfo {
-                        driver: odbc_driver[idx].map(|x| {
-                            x.clone()
-                        }).ok_or_else(|| CommonError::new(&CommonDefaultErrorKind::NoData, "odbc_driver not found"))?,
+                        driver: odbc_driver[idx].map(|x| x.clone()),
                         current_time_query: odbc_current_time_query[idx].map(|x| {
                             x.clone()
                         }).ok_or_else(|| CommonError::new(&CommonDefaultErrorKind::NoData, "odbc_driver not found"))?,
                         current_time_col_name: odbc_current_time_col_name[idx].map(|x| {
                             x.clone()
                         }).ok_or_else(|| CommonError::new(&CommonDefaultErrorKind::NoData, "odbc_driver not found"))?,
+                        connection_string: odbc_connection_string[idx].map(|x| x.clone()),
+                        dsn: odbc_dsn[idx].map(|x| x.clone()),
+                        attributes: odbc_attributes[idx].map(|x| {
+                            serde_json::from_str(x.as_str()).map_err(|e| {
+                                CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("{} odbc_attributes parse failed : {}", name[idx], e))
+                            })
+                        }).transpose()?,
+                    })
+                } else {
+                    None
+                },
+                health: if health_interval_sec[idx].is_some() || health_failure_threshold[idx].is_some() || health_ping_query[idx].is_some() {
+                    let default = ConnectionHealthInfo::default();
+                    Some(ConnectionHealthInfo {
+                        interval_sec: health_interval_sec[idx].map_or(default.interval_sec, |x| *x as u64),
+                        failure_threshold: health_failure_threshold[idx].map_or(default.failure_threshold, |x| *x as u32),
+                        ping_query: health_ping_query[idx].map(|x| x.clone()),
+                    })
+                } else {
+                    None
+                },
+                topology: if replica_addr[idx].is_some() || target_session_attrs[idx].is_some() {
+                    Some(ConnectionTopologyInfo {
+                        replicas: replica_addr[idx].map(|x| {
+                            x.split(|x| {x == ','}).map(|x| x.trim().to_string()).filter(|x| !x.is_empty()).collect()
+                        }),
+                        target_session_attrs: target_session_attrs[idx].map(|x| x.clone()),
+                    })
+                } else {
+                    None
+                },
+                tls: if tls_mode[idx].is_some() || tls_ca_file[idx].is_some() || tls_cert_file[idx].is_some() || tls_key_file[idx].is_some() || tls_server_name[idx].is_some() {
+                    Some(ConnectionTlsInfo {
+                        sslmode: tls_mode[idx].map(|x| x.clone()),
+                        ca_file: tls_ca_file[idx].map(|x| x.clone()),
+                        cert_file: tls_cert_file[idx].map(|x| x.clone()),
+                        key_file: tls_key_file[idx].map(|x| x.clone()),
+                        server_name: tls_server_name[idx].map(|x| x.clone()),
                     })
                 } else {
                     None
@@ -379,6 +666,8 @@ impl ConfLoader for PairDbLoader {
 
             root.connection.insert(name[idx].clone(), info);
         }
+        crate::tls::validate_conn_tls(&root)?;
+        crate::secret::resolve_conn_secret(self.secret_key.as_ref(), &mut root)?;
 
         if self.once_init_flag {
             self.once_cache.1.get_or_init(|| {root.clone()});
@@ -422,4 +711,37 @@ impl ConfLoader for PairDbLoader {
 
         Ok(root)
     }
-}
\ No newline at end of file
+
+    fn load_watermark(&self, plan_name : &'_ str) -> Result<Option<String>, CommonError> {
+        let mut item = get_pair_db_connection!(self)?;
+        let conn = item.get_value();
+
+        let param = PairValueEnum::Array(vec![
+            PairValueEnum::String(self.identifier.clone()),
+            PairValueEnum::String(plan_name.to_string())
+        ]);
+
+        let data = conn.execute_pair(self.watermark_query.0, &param).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "", e)
+        })?;
+
+        let value = utils::get_col_ref!("value", &data, str)?;
+        Ok(value.first().map(|x| (*x).clone()))
+    }
+
+    fn save_watermark(&self, plan_name : &'_ str, value : &'_ str) -> Result<(), CommonError> {
+        let mut item = get_pair_db_connection!(self)?;
+        let conn = item.get_value();
+
+        let param = PairValueEnum::Array(vec![
+            PairValueEnum::String(self.identifier.clone()),
+            PairValueEnum::String(plan_name.to_string()),
+            PairValueEnum::String(value.to_string())
+        ]);
+
+        conn.execute_pair(self.watermark_query.1, &param).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "", e)
+        })?;
+        Ok(())
+    }
+}
@@ -26,18 +26,65 @@ SELECT
     p.type_name             AS type,
     p.interval_connection   as interval_connection,
     p.interval_second      AS interval_second,
+    p."transaction"        AS plan_transaction,
+    p.watermark_step       AS plan_watermark_step,
+    p.watermark_column     AS plan_watermark_column,
+    p.watermark_store      AS plan_watermark_store,
+    p.watermark_path       AS plan_watermark_path,
+    p.watermark_initial    AS plan_watermark_initial,
     pc.id                   AS chain_id,
     pc.next_chain_id        AS chain_next_id,
     pc.connection           AS chain_connection,
     pc.query               AS chain_query,
+    pc.when_expr           AS chain_when,
+    pc.mode                AS chain_mode,
+    pc.batch_size          AS chain_batch_size,
+    pc.source_row_col      AS chain_source_row_col,
+    pc.tx_group            AS chain_tx_group,
+    pc.kind                AS chain_kind,
+    pc.script_lang         AS chain_script_lang,
+    pc.script_file         AS chain_script_file,
+    pc.script_func         AS chain_script_func,
+    pc.after_ids           AS chain_after_ids,
+    pc.bind_mode           AS chain_bind_mode,
+    pc.lookup_key          AS chain_lookup_key,
+    pc.sink_step           AS chain_sink_step,
+    pc.sink_format         AS chain_sink_format,
+    pc.sink_path           AS chain_sink_path,
+    pc.sink_columns        AS chain_sink_columns,
+    pc.sink_gzip           AS chain_sink_gzip,
+    pc.sink_rotate_rows    AS chain_sink_rotate_rows,
+    pc.assert_step         AS chain_assert_step,
+    pc.assert_min_rows     AS chain_assert_min_rows,
+    pc.assert_max_rows     AS chain_assert_max_rows,
+    pc.assert_not_null     AS chain_assert_not_null,
+    pc.assert_unique       AS chain_assert_unique,
+    pc.assert_range        AS chain_assert_range,
+    pc.assert_freshness_column AS chain_assert_freshness_column,
+    pc.assert_freshness_sec AS chain_assert_freshness_sec,
+    pc.paginate_mode       AS chain_paginate_mode,
+    pc.page_size           AS chain_page_size,
+    pc.paginate_column     AS chain_paginate_column,
+    pc.paginate_initial    AS chain_paginate_initial,
+    pc.paginate_type       AS chain_paginate_type,
+    pc.redis_rule          AS chain_redis_rule,
+    pc.on_row_error        AS chain_on_row_error,
+    pc.dead_letter_connection AS chain_dead_letter_connection,
+    pc.dead_letter_table   AS chain_dead_letter_table,
+    pc.dead_letter_path    AS chain_dead_letter_path,
+    pc.read_only           AS chain_read_only,
     m.mapping_type         AS mapping_type,
     m.ranking              AS mapping_ranking,
     pa.data                 AS arg_data,
     pa.idx                  AS arg_idx,
+    pa."name"               AS arg_name,
+    pa."type"               AS arg_type,
     pb.bind_id             AS bind_id,
     pb.key                  AS bind_key,
     pb.row                  AS bind_row,
     pb.idx                  AS bind_idx,
+    pb."name"               AS bind_name,
+    pb."cast"               AS bind_cast,
     ps.lang                 AS script_lang,
     ps."file"                 AS script_file
 FROM mypip_plan p
@@ -79,9 +126,26 @@ select
 	conn_addr,
 	conn_passwd,
 	conn_timeout,
+	min_idle,
+	idle_timeout_sec,
+	max_lifetime_sec,
+	acquire_timeout_ms,
 	odbc_driver,
 	odbc_current_time_query,
-	odbc_current_time_col_name
+	odbc_current_time_col_name,
+	odbc_connection_string,
+	odbc_dsn,
+	odbc_attributes,
+	health_interval_sec,
+	health_failure_threshold,
+	health_ping_query,
+	replica_addr,
+	target_session_attrs,
+	tls_mode,
+	tls_ca_file,
+	tls_cert_file,
+	tls_key_file,
+	tls_server_name
 from
 	mypip_connection_info
 where
@@ -108,11 +172,7 @@ macro_rules! vec_if_same_len {
     ($($vec:expr),+) => {{
         let lengths = vec![$($vec.len()),+];
         let first = lengths[0];
-        if !lengths.iter().all(|&l| l == first) {
-            true
-        } else {
-            false
-        }
+        lengths.iter().all(|&l| l == first)
     }};
 }
 
@@ -152,6 +212,66 @@ macro_rules! get_col_ref {
             CommonError::extend(&CommonDefaultErrorKind::ParsingFail, $col, e)
         })
     };
+
+    ($col:expr, $data:expr, bool, null) => {
+        $crate::pair_db_loader::utils::get_col_ref_bool_null($col, $data).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ParsingFail, $col, e)
+        })
+    };
+}
+
+macro_rules! watermark_select_query {
+    ($identifier_expr:expr, $plan_expr:expr) => {
+        concat!(
+r#"
+select
+	"value"
+from
+	mypip_plan_watermark
+where
+	identifier = "#, $identifier_expr, r#"
+	AND plan_name = "#, $plan_expr,
+        )
+    };
+}
+
+macro_rules! watermark_upsert_query {
+    ($identifier_expr:expr, $plan_expr:expr, $value_expr:expr) => {
+        concat!(
+r#"
+insert into mypip_plan_watermark(identifier, plan_name, "value", updated_at)
+values ("#, $identifier_expr, ", ", $plan_expr, ", ", $value_expr, r#", CURRENT_TIMESTAMP)
+on conflict (identifier, plan_name)
+do update set "value" = EXCLUDED."value", updated_at = EXCLUDED.updated_at"#,
+        )
+    };
+}
+
+macro_rules! conn_passwd_select_query {
+    ($identifier_expr:expr) => {
+        concat!(
+r#"
+select
+	"name",
+	conn_passwd
+from
+	mypip_connection_info
+where
+	identifier = "#, $identifier_expr,
+        )
+    };
+}
+
+macro_rules! conn_passwd_update_query {
+    ($passwd_expr:expr, $identifier_expr:expr, $name_expr:expr) => {
+        concat!(
+r#"
+update mypip_connection_info set conn_passwd = "#, $passwd_expr, r#"
+where
+	identifier = "#, $identifier_expr, r#"
+	AND "name" = "#, $name_expr,
+        )
+    };
 }
 
 pub(crate) fn get_col_ref_str<'a>(col:  &'a str, data :  &'a PairValueEnum) -> Result<Vec<&'a String>, CommonError> {
@@ -292,9 +412,85 @@ pub(crate) fn get_col_ref_i64_null<'a>(col:  &'a str, data :  &'a PairValueEnum)
     }
 }
 
+pub(crate) fn get_col_ref_bool_null<'a>(col:  &'a str, data :  &'a PairValueEnum) -> Result<Vec<Option<&'a bool>>, CommonError> {
+    if let PairValueEnum::Map(map) = data {
+        if let Some(PairValueEnum::Array(vec)) = map.get(col) {
+            let mut res = Vec::with_capacity(vec.len());
+            for r in vec {
+                match r {
+                    PairValueEnum::Bool(s) => {
+                        res.push(Some(s))
+                    },
+                    PairValueEnum::Null => {
+                        res.push(None)
+                    },
+                    _ => {
+                        return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("bool != {:?}", r)).to_result()
+                    }
+                }
+            }
+            Ok(res)
+        } else {
+            CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("array != {:?}", data)).to_result()
+        }
+    } else {
+        CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("map != {:?}", data)).to_result()
+    }
+}
+
+pub(crate) const PLAN_SQLITE_I32_COLS : [&'static str; 10] = ["interval_second", "chain_batch_size", "chain_sink_rotate_rows",
+    "chain_assert_min_rows", "chain_assert_max_rows", "chain_assert_freshness_sec", "chain_page_size", "arg_idx", "bind_row", "bind_idx"];
+pub(crate) const PLAN_SQLITE_BOOL_COLS : [&'static str; 3] = ["plan_transaction", "chain_sink_gzip", "chain_read_only"];
+pub(crate) const CONN_SQLITE_I32_COLS : [&'static str; 8] = ["max_size", "conn_timeout", "min_idle", "idle_timeout_sec",
+    "max_lifetime_sec", "acquire_timeout_ms", "health_interval_sec", "health_failure_threshold"];
+
+pub(crate) fn coerce_sqlite_cols(data : &'_ mut PairValueEnum, i32_cols : &'_ [&'_ str], bool_cols : &'_ [&'_ str]) -> Result<(), CommonError> {
+    let map = match data {
+        PairValueEnum::Map(map) => map,
+        PairValueEnum::Null => return Ok(()),
+        other => {
+            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("map != {:?}", other)).to_result()
+        }
+    };
+
+    for col in i32_cols {
+        if let Some(PairValueEnum::Array(vec)) = map.get_mut(*col) {
+            for v in vec.iter_mut() {
+                if let PairValueEnum::BigInt(i) = v {
+                    let narrow = i32::try_from(*i).map_err(|_| {
+                        CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} out of i32 range {}", col, i))
+                    })?;
+                    *v = PairValueEnum::Int(narrow);
+                }
+            }
+        }
+    }
+
+    for col in bool_cols {
+        if let Some(PairValueEnum::Array(vec)) = map.get_mut(*col) {
+            for v in vec.iter_mut() {
+                if let PairValueEnum::BigInt(i) = v {
+                    *v = match *i {
+                        0 => PairValueEnum::Bool(false),
+                        1 => PairValueEnum::Bool(true),
+                        other => {
+                            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} not bool {}", col, other)).to_result()
+                        }
+                    };
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 pub(super) use vec_if_same_len;
 pub(super) use plan_select_query;
 pub(super) use conn_select_query;
 pub(super) use script_data_select_query;
 pub(super) use get_col_ref;
-pub(super) use plan_toml_select_query;
\ No newline at end of file
+pub(super) use plan_toml_select_query;
+pub(super) use watermark_select_query;
+pub(super) use watermark_upsert_query;
+pub(super) use conn_passwd_select_query;
+pub(super) use conn_passwd_update_query;
\ No newline at end of file
@@ -0,0 +1,113 @@
+use std::path::Path;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce};
+use chacha20poly1305::aead::{Aead, AeadCore, OsRng};
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use mypip_types::config::conn::ConnectionInfos;
+
+pub const SECRET_PREFIX : &'static str = "enc:";
+const NONCE_SIZE : usize = 12;
+
+#[derive(Clone)]
+pub struct SecretKey {
+    cipher : ChaCha20Poly1305
+}
+
+impl SecretKey {
+    pub fn load(path : &'_ str) -> Result<Self, CommonError> {
+        let data = std::fs::read_to_string(path).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("read secret key {} failed : {}", path, e))
+        })?;
+        let raw = STANDARD.decode(data.trim()).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("secret key {} not base64 : {}", path, e))
+        })?;
+        if raw.len() != 32 {
+            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("secret key {} must be 32 bytes, got {}", path, raw.len())).to_result();
+        }
+
+        Ok(SecretKey { cipher : ChaCha20Poly1305::new(Key::from_slice(raw.as_slice())) })
+    }
+
+    pub fn generate(path : &'_ str) -> Result<Self, CommonError> {
+        if Path::new(path).exists() {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("secret key {} already exists", path)).to_result();
+        }
+
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        write_key_file(path, STANDARD.encode(key.as_slice()).as_str())?;
+        Ok(SecretKey { cipher : ChaCha20Poly1305::new(&key) })
+    }
+
+    pub fn encrypt(&self, plain : &'_ str) -> Result<String, CommonError> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let cipher_text = self.cipher.encrypt(&nonce, plain.as_bytes()).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("secret encrypt failed : {}", e))
+        })?;
+
+        let mut data = nonce.to_vec();
+        data.extend(cipher_text);
+        Ok(format!("{}{}", SECRET_PREFIX, STANDARD.encode(data)))
+    }
+
+    pub fn decrypt(&self, value : &'_ str) -> Result<String, CommonError> {
+        let encoded = value.strip_prefix(SECRET_PREFIX).ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, "secret value need enc: prefix")
+        })?;
+        let data = STANDARD.decode(encoded.trim()).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("secret value not base64 : {}", e))
+        })?;
+        if data.len() <= NONCE_SIZE {
+            return CommonError::new(&CommonDefaultErrorKind::ParsingFail, "secret value too short").to_result();
+        }
+
+        let (nonce, cipher_text) = data.split_at(NONCE_SIZE);
+        let plain = self.cipher.decrypt(Nonce::from_slice(nonce), cipher_text).map_err(|_| {
+            CommonError::new(&CommonDefaultErrorKind::ExecuteFail, "secret decrypt failed, wrong key or broken value")
+        })?;
+        String::from_utf8(plain).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("secret value not utf-8 : {}", e))
+        })
+    }
+}
+
+#[cfg(unix)]
+fn write_key_file(path : &'_ str, data : &'_ str) -> Result<(), CommonError> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut f = std::fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(path).map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("create secret key {} failed : {}", path, e))
+    })?;
+    writeln!(f, "{}", data).map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("write secret key {} failed : {}", path, e))
+    })
+}
+
+#[cfg(not(unix))]
+fn write_key_file(path : &'_ str, data : &'_ str) -> Result<(), CommonError> {
+    std::fs::write(path, format!("{}\n", data)).map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("write secret key {} failed : {}", path, e))
+    })
+}
+
+pub fn resolve_secret(key : Option<&SecretKey>, value : &'_ str) -> Result<String, CommonError> {
+    if !value.starts_with(SECRET_PREFIX) {
+        return Ok(value.to_string());
+    }
+
+    match key {
+        Some(k) => k.decrypt(value),
+        None => CommonError::new(&CommonDefaultErrorKind::NoData, "enc: value need secret key file").to_result()
+    }
+}
+
+pub fn resolve_conn_secret(key : Option<&SecretKey>, infos : &mut ConnectionInfos) -> Result<(), CommonError> {
+    for (name, info) in infos.connection.iter_mut() {
+        info.conn_passwd = resolve_secret(key, info.conn_passwd.as_str()).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("{} conn_passwd decrypt failed", name), e)
+        })?;
+    }
+    Ok(())
+}
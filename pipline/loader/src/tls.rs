@@ -0,0 +1,88 @@
+use std::path::Path;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use mypip_types::config::conn::{ConnectionInfos, ConnectionTlsInfo};
+
+const TLS_SSLMODES : [&'static str; 6] = ["disable", "allow", "prefer", "require", "verify-ca", "verify-full"];
+
+fn check_file(name : &'_ str, key : &'_ str, path : Option<&String>) -> Result<(), CommonError> {
+    if let Some(p) = path {
+        if !Path::new(p.as_str()).is_file() {
+            return CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} tls {} not exists file {}", name, key, p)).to_result();
+        }
+    }
+    Ok(())
+}
+
+pub fn validate_tls(name : &'_ str, tls : &'_ ConnectionTlsInfo) -> Result<(), CommonError> {
+    let mode = tls.sslmode.as_deref().unwrap_or("require");
+    if !TLS_SSLMODES.contains(&mode) {
+        return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} not support tls sslmode {}", name, mode)).to_result();
+    }
+    if (mode == "verify-ca" || mode == "verify-full") && tls.ca_file.is_none() {
+        return CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} tls sslmode {} need ca_file", name, mode)).to_result();
+    }
+    if tls.cert_file.is_some() != tls.key_file.is_some() {
+        return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} tls cert_file and key_file must be set together", name)).to_result();
+    }
+    if tls.server_name.as_ref().is_some_and(|x| x.is_empty()) {
+        return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} tls server_name is empty", name)).to_result();
+    }
+
+    check_file(name, "ca_file", tls.ca_file.as_ref())?;
+    check_file(name, "cert_file", tls.cert_file.as_ref())?;
+    check_file(name, "key_file", tls.key_file.as_ref())
+}
+
+pub fn is_tls_enabled(tls : Option<&ConnectionTlsInfo>) -> bool {
+    tls.is_some_and(|x| x.sslmode.as_deref() != Some("disable"))
+}
+
+pub fn check_tls_applicable(name : &'_ str, conn_type : &'_ str, tls : Option<&ConnectionTlsInfo>) -> Result<(), CommonError> {
+    let tls = match tls {
+        Some(t) => t,
+        None => return Ok(())
+    };
+    validate_tls(name, tls)?;
+    if !is_tls_enabled(Some(tls)) {
+        return Ok(());
+    }
+
+    let mode = tls.sslmode.as_deref().unwrap_or("require");
+    match conn_type {
+        "postgres" => Ok(()),
+        // redis and scylla have no plain text fallback, so the opportunistic modes can not be honoured
+        "redis" | "scylla" if mode == "allow" || mode == "prefer" => {
+            CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} {} not support tls sslmode {}, use require, verify-ca or verify-full", name, conn_type, mode)).to_result()
+        },
+        "redis" | "scylla" => Ok(()),
+        "odbc" => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} odbc not support tls, use odbc attributes", name)).to_result(),
+        _ => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} {} not support tls", name, conn_type)).to_result()
+    }
+}
+
+pub fn create_tls_extend(tls : Option<&ConnectionTlsInfo>) -> Option<Vec<String>> {
+    let tls = tls.filter(|x| is_tls_enabled(Some(*x)))?;
+    let mut v = vec![format!("sslmode={}", tls.sslmode.as_deref().unwrap_or("require"))];
+
+    if let Some(ca) = tls.ca_file.as_ref() {
+        v.push(format!("sslrootcert={}", ca));
+    }
+    if let Some(cert) = tls.cert_file.as_ref() {
+        v.push(format!("sslcert={}", cert));
+    }
+    if let Some(key) = tls.key_file.as_ref() {
+        v.push(format!("sslkey={}", key));
+    }
+    if let Some(server_name) = tls.server_name.as_ref() {
+        v.push(format!("server_name={}", server_name));
+    }
+    Some(v)
+}
+
+pub fn validate_conn_tls(infos : &'_ ConnectionInfos) -> Result<(), CommonError> {
+    for (name, info) in infos.connection.iter() {
+        check_tls_applicable(name.as_str(), info.conn_type.as_str(), info.tls.as_ref())?;
+    }
+    Ok(())
+}
@@ -5,6 +5,7 @@ use mypip_types::config::app::AppConfig;
 use mypip_types::interface::ConfLoader;
 use mypip_types::config::plan::*;
 use mypip_types::config::conn::*;
+use crate::secret::SecretKey;
 
 pub struct TomlFileConfLoader {
     root_path : String,
@@ -12,6 +13,7 @@ pub struct TomlFileConfLoader {
 
     identifier : String,
     is_once_load : bool,
+    secret_key : Option<SecretKey>,
     once_cache : (OnceLock<PlanRoot>, OnceLock<ConnectionInfos>, OnceLock<AppConfig>, OnceLock<HashMap<String,String>>),
 }
 
@@ -20,11 +22,17 @@ impl TomlFileConfLoader {
         TomlFileConfLoader {
             root_path : root,
             is_once_load : load_once,
+            secret_key : None,
             identifier,
             script_dir,
             once_cache : (OnceLock::new(), OnceLock::new(), OnceLock::new(), OnceLock::new()), }
     }
 
+    pub fn with_secret_key(mut self, key : Option<SecretKey>) -> Self {
+        self.secret_key = key;
+        self
+    }
+
     pub fn read_data(&self, data_file : String) -> Result<String, CommonError> {
         let p = std::path::Path::new(&self.root_path).join(data_file);
         match std::fs::read_to_string(p) {
@@ -76,7 +84,9 @@ impl ConfLoader for TomlFileConfLoader {
             let c = self.once_cache.1.get();
             if c.is_none() {
                 let data = self.read_data("conn.toml".to_string())?;
-                let root : ConnectionInfos = self.parsing_data(data.as_str())?;
+                let mut root : ConnectionInfos = self.parsing_data(data.as_str())?;
+                crate::tls::validate_conn_tls(&root)?;
+                crate::secret::resolve_conn_secret(self.secret_key.as_ref(), &mut root)?;
                 let _ = self.once_cache.1.set(root.clone());
                 Ok(root)
             } else {
@@ -85,7 +95,9 @@ impl ConfLoader for TomlFileConfLoader {
         }
         else {
             let data = self.read_data("conn.toml".to_string())?;
-            let root : ConnectionInfos = self.parsing_data(data.as_str())?;
+            let mut root : ConnectionInfos = self.parsing_data(data.as_str())?;
+            crate::tls::validate_conn_tls(&root)?;
+            crate::secret::resolve_conn_secret(self.secret_key.as_ref(), &mut root)?;
             Ok(root)
         };
 
@@ -105,10 +117,16 @@ impl ConfLoader for TomlFileConfLoader {
 
         let mut map = HashMap::new();
         for (name, p) in plans.plan {
-            if let Some(script) = p.script {
+            let chain_scripts = p.chain.unwrap_or_default().into_iter().filter_map(|x| x.script);
+
+            for script in p.script.into_iter().chain(chain_scripts) {
+                if map.contains_key(&script.file) {
+                    continue;
+                }
+
                 let script_path = std::path::Path::new(self.script_dir.as_str()).join(script.file.as_str());
                 let data = std::fs::read_to_string(script_path).map_err(|e| {
-                    CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("read failed script {}, {}", script.file.as_str(), e))
+                    CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("read failed script {} {}, {}", name, script.file.as_str(), e))
                 })?;
 
                 map.insert(script.file, data);
@@ -121,4 +139,11 @@ impl ConfLoader for TomlFileConfLoader {
         
         Ok(map)
     }
+    fn load_watermark(&self, plan_name : &'_ str) -> Result<Option<String>, CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("file loader not support db watermark {}", plan_name)).to_result()
+    }
+
+    fn save_watermark(&self, plan_name : &'_ str, _value : &'_ str) -> Result<(), CommonError> {
+        CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("file loader not support db watermark {}", plan_name)).to_result()
+    }
 }
\ No newline at end of file
@@ -1,6 +1,9 @@
 use common_rs::c_err::CommonError;
 use common_rs::init::InitConfig;
 use common_rs::init::LoggerConf;
+use common_rs::exec::interfaces::pair::{PairExecutor, PairExecutorInfo, PairValueEnum};
+use mypip_exec::sqlite::SqlitePairExecutor;
+use mypip_loader::pair_db_loader::rdb::PairDbLoader;
 use mypip_loader::toml_file_loader;
 use mypip_types::interface::ConfLoader;
 
@@ -22,4 +25,126 @@ fn load_conn_toml_file_loader() -> Result<(), CommonError> {
     loader.load_plan()?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+const INIT_SQL : &'static str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets/sql/init.sql"));
+
+const SQLITE_SEED_SQL : [&'static str; 6] = [
+    r#"insert into mypip_connection_info(identifier, id, max_size, "name", conn_type, conn_name, conn_user, conn_addr, conn_passwd, conn_timeout, min_idle)
+       values ('test', 1, 5, 'local', 'sqlite', 'local', '', '/tmp/local.db', '', 10, 1)"#,
+    r#"insert into mypip_plan(identifier, id, "name", type_name, "enable", "transaction", interval_second, watermark_step, watermark_column)
+       values ('test', 1, 'copy', 'query', true, true, 30, 'c1', 'updated_at')"#,
+    r#"insert into mypip_plan_chain(id, plan_id, next_chain_id, connection, query, batch_size, sink_gzip, read_only,
+       assert_step, assert_min_rows, assert_not_null, assert_range)
+       values ('c1', 1, '0', 'local', 'select 1', 100, false, true, 'c1', 1, 'id, name', 'amount:0:,score::10')"#,
+    r#"insert into mypip_plan_chain_mapping(chain_id, mapping_type, ranking, args_or_bind_id)
+       values ('c1', 'args', 0, 1)"#,
+    r#"insert into mypip_plan_chain_args(id, chain_id, "data", idx)
+       values (1, 'c1', 'x', 0)"#,
+    r#"insert into mypip_plan_script_data(identifier, script_file, script_data)
+       values ('test', 'a.lua', 'return 1')"#,
+];
+
+#[test]
+fn load_sqlite_pair_db_loader() -> Result<(), CommonError> {
+    let base = std::env::temp_dir().join(format!("mypip_loader_sqlite_{}", std::process::id()));
+    let conf_dir = base.join("test");
+    let db_path = base.join("conf.db").to_string_lossy().to_string();
+    let _ = std::fs::remove_dir_all(base.as_path());
+    std::fs::create_dir_all(conf_dir.as_path()).expect("create conf dir failed");
+
+    std::fs::write(conf_dir.join("app.toml"), format!(r#"
+[log]
+log_type = "console"
+log_level = "info"
+
+[db_config]
+db_type = "sqlite"
+db_address = "{}"
+db_name = ""
+db_user = ""
+db_password = ""
+db_ttl = 0
+"#, db_path)).expect("write app.toml failed");
+
+    let mut exec = SqlitePairExecutor::new(&PairExecutorInfo {
+        addr: vec![format!("file:{}?mode=rwc", db_path)],
+        name: String::new(),
+        user: String::new(),
+        password: String::new(),
+        timeout_sec: 10,
+        extend: None,
+    })?;
+    for stmt in INIT_SQL.split(';').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        exec.execute_pair(stmt, &PairValueEnum::Null)?;
+    }
+    for stmt in SQLITE_SEED_SQL {
+        exec.execute_pair(stmt, &PairValueEnum::Null)?;
+    }
+
+    let loader : Box<dyn ConfLoader> = Box::new(PairDbLoader::new("test".to_string(), base.to_string_lossy().as_ref(), false, false, None)?);
+
+    let conn = loader.load_connection()?;
+    let local = conn.connection.get("local").expect("local connection not loaded");
+    assert_eq!(local.max_size, 5);
+    assert_eq!(local.conn_timeout, 10);
+    assert_eq!(local.min_idle, Some(1));
+
+    let plan = loader.load_plan()?;
+    let copy = plan.plan.get("copy").expect("copy plan not loaded");
+    assert_eq!(copy.interval.second, 30);
+    assert!(copy.transaction);
+
+    let wm = copy.watermark.as_ref().expect("copy watermark not loaded");
+    assert_eq!(wm.step, "c1");
+    assert_eq!(wm.column, "updated_at");
+    assert_eq!(wm.store, "db");
+
+    let chain = copy.chain.as_ref().expect("copy chain not loaded");
+    assert_eq!(chain.len(), 1);
+    assert_eq!(chain[0].batch_size, Some(100));
+    assert_eq!(chain[0].read_only, Some(true));
+    assert!(chain[0].sink.is_none());
+
+    let rule = chain[0].assertion.as_ref().expect("c1 assertion not loaded");
+    assert_eq!(rule.min_rows, Some(1));
+    assert_eq!(rule.not_null, Some(vec!["id".to_string(), "name".to_string()]));
+    let range = rule.range.as_ref().expect("c1 assert range not loaded");
+    assert_eq!(range.len(), 2);
+    assert_eq!((range[0].column.as_str(), range[0].min, range[0].max), ("amount", Some(0.0), None));
+    assert_eq!((range[1].column.as_str(), range[1].min, range[1].max), ("score", None, Some(10.0)));
+
+    let scripts = loader.load_script_data()?;
+    assert_eq!(scripts.get("a.lua").map(|x| x.as_str()), Some("return 1"));
+
+    let _ = std::fs::remove_dir_all(base.as_path());
+    Ok(())
+}
+
+#[test]
+fn check_tls_by_conn_type() {
+    use mypip_loader::tls::{check_tls_applicable, create_tls_extend};
+    use mypip_types::config::conn::ConnectionTlsInfo;
+
+    let tls = |mode : &'_ str| ConnectionTlsInfo {
+        sslmode : Some(mode.to_string()),
+        ca_file : None,
+        cert_file : None,
+        key_file : None,
+        server_name : Some(String::from("db.local")),
+    };
+
+    assert!(check_tls_applicable("a", "postgres", Some(&tls("prefer"))).is_ok());
+    assert!(check_tls_applicable("a", "redis", Some(&tls("require"))).is_ok());
+    assert!(check_tls_applicable("a", "scylla", Some(&tls("prefer"))).is_err());
+    assert!(check_tls_applicable("a", "sqlite", Some(&tls("require"))).is_err());
+    assert!(check_tls_applicable("a", "sqlite", Some(&tls("disable"))).is_ok());
+    assert!(check_tls_applicable("a", "postgres", Some(&tls("verify-full"))).is_err());
+
+    let mut missing = tls("require");
+    missing.ca_file = Some(String::from("/not/exists/ca.pem"));
+    assert!(check_tls_applicable("a", "postgres", Some(&missing)).is_err());
+
+    assert_eq!(create_tls_extend(Some(&tls("require"))), Some(vec![String::from("sslmode=require"), String::from("server_name=db.local")]));
+    assert_eq!(create_tls_extend(Some(&tls("disable"))), None);
+}
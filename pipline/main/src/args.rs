@@ -1,20 +1,58 @@
 use std::fs;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 use common_rs::c_err::CommonError;
 use common_rs::c_err::gen::CommonDefaultErrorKind;
 use mypip_types::config::app::AppConfig;
 
 #[derive(Parser, Debug)]
+#[command(subcommand_negates_reqs = true)]
 pub struct Args {
-    #[clap(long)]
-    pub base_dir : String,
-    #[clap(long)]
-    pub identifier : String,
+    #[clap(long, required = true)]
+    pub base_dir : Option<String>,
+    #[clap(long, required = true)]
+    pub identifier : Option<String>,
     #[clap(long, default_value_t = true)]
     pub once_conf_load  : bool,
     #[clap(long, default_value = "file")]
-    pub loader_type     : String
+    pub loader_type     : String,
+    #[clap(long)]
+    pub secret_key_file : Option<String>,
+
+    #[clap(subcommand)]
+    pub command : Option<Command>
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    #[clap(subcommand)]
+    Secret(SecretCommand)
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SecretCommand {
+    /// encrypt values to `enc:`, the key file is created when not exists
+    Encrypt {
+        #[clap(long)]
+        key_file : String,
+        #[clap(long, required = true)]
+        value : Vec<String>
+    },
+    /// re-key `enc:` values, every `enc:` string value in a toml file, or conn_passwd rows of the db loader
+    Rotate {
+        #[clap(long)]
+        key_file : String,
+        #[clap(long)]
+        new_key_file : String,
+        #[clap(long)]
+        value : Vec<String>,
+        #[clap(long)]
+        file : Option<String>,
+        #[clap(long, requires = "db_identifier")]
+        db_base_dir : Option<String>,
+        #[clap(long, requires = "db_base_dir")]
+        db_identifier : Option<String>
+    }
 }
 
 pub fn parsing() -> Args {
@@ -1,4 +1,5 @@
 mod args;
+mod secret;
 
 use serde::{Deserialize, Serialize};
 use common_rs::c_err::CommonError;
@@ -14,9 +15,19 @@ use common_rs::logger::log_info;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let proc_args = args::parsing();
-    let app_config = args::load_app_config(proc_args.base_dir.as_str())?;
+    if let Some(args::Command::Secret(cmd)) = proc_args.command {
+        secret::run_secret(cmd)?;
+        return Ok(());
+    }
+
+    let base_dir = proc_args.base_dir.ok_or_else(|| CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, "need --base-dir"))?;
+    let identifier = proc_args.identifier.ok_or_else(|| CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, "need --identifier"))?;
+    let mut app_config = args::load_app_config(base_dir.as_str())?;
+    if proc_args.secret_key_file.is_some() {
+        app_config.secret_key_file = proc_args.secret_key_file;
+    }
     
-    GLOBAL.initialize(proc_args.identifier, proc_args.base_dir, proc_args.loader_type, proc_args.once_conf_load, app_config)?;
+    GLOBAL.initialize(identifier, base_dir, proc_args.loader_type, proc_args.once_conf_load, app_config)?;
 
     let mut cancel = PlanThreadExecutor::daemon();
 
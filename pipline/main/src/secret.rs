@@ -0,0 +1,199 @@
+use std::path::Path;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use mypip_loader::pair_db_loader::rdb::PairDbLoader;
+use mypip_loader::secret::{SecretKey, SECRET_PREFIX};
+use crate::args::SecretCommand;
+
+fn is_secret_char(c : char) -> bool {
+    c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '='
+}
+
+fn rotate_value(old : &'_ SecretKey, new : &'_ SecretKey, value : &'_ str) -> Result<String, CommonError> {
+    let plain = old.decrypt(value)?;
+    new.encrypt(plain.as_str())
+}
+
+fn rotate_toml_value(old : &'_ SecretKey, new : &'_ SecretKey, key : &'_ str, v : &'_ mut toml_edit::Value, cnt : &'_ mut usize, errs : &'_ mut Vec<String>) {
+    match v {
+        toml_edit::Value::String(s) if s.value().starts_with(SECRET_PREFIX) => {
+            let body = &s.value()[SECRET_PREFIX.len()..];
+            let rotated = match !body.is_empty() && body.chars().all(is_secret_char) {
+                true => rotate_value(old, new, s.value()),
+                false => CommonError::new(&CommonDefaultErrorKind::ParsingFail, "malformed secret value").to_result()
+            };
+
+            match rotated {
+                Ok(x) => {
+                    let decor = v.decor().clone();
+                    *v = toml_edit::Value::from(x);
+                    *v.decor_mut() = decor;
+                    *cnt += 1;
+                },
+                Err(e) => errs.push(format!("{} : {}", key, e))
+            }
+        },
+        toml_edit::Value::Array(arr) => {
+            for (idx, item) in arr.iter_mut().enumerate() {
+                rotate_toml_value(old, new, format!("{}[{}]", key, idx).as_str(), item, cnt, errs);
+            }
+        },
+        toml_edit::Value::InlineTable(t) => {
+            for (k, item) in t.iter_mut() {
+                rotate_toml_value(old, new, format!("{}.{}", key, k.get()).as_str(), item, cnt, errs);
+            }
+        },
+        _ => {}
+    }
+}
+
+fn rotate_toml_table(old : &'_ SecretKey, new : &'_ SecretKey, prefix : &'_ str, t : &'_ mut toml_edit::Table, cnt : &'_ mut usize, errs : &'_ mut Vec<String>) {
+    for (k, item) in t.iter_mut() {
+        let key = if prefix.is_empty() { k.get().to_string() } else { format!("{}.{}", prefix, k.get()) };
+        match item {
+            toml_edit::Item::Value(v) => rotate_toml_value(old, new, key.as_str(), v, cnt, errs),
+            toml_edit::Item::Table(sub) => rotate_toml_table(old, new, key.as_str(), sub, cnt, errs),
+            toml_edit::Item::ArrayOfTables(arr) => {
+                for (idx, sub) in arr.iter_mut().enumerate() {
+                    rotate_toml_table(old, new, format!("{}[{}]", key, idx).as_str(), sub, cnt, errs);
+                }
+            },
+            toml_edit::Item::None => {}
+        }
+    }
+}
+
+// any value failing to rotate aborts the whole file, so it never mixes old-key and new-key values
+fn rotate_toml(old : &'_ SecretKey, new : &'_ SecretKey, path : &'_ str, data : &'_ str) -> Result<(String, usize), CommonError> {
+    let mut doc = data.parse::<toml_edit::DocumentMut>().map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("parse {} failed : {}", path, e))
+    })?;
+
+    let mut cnt = 0;
+    let mut errs = Vec::new();
+    rotate_toml_table(old, new, "", doc.as_table_mut(), &mut cnt, &mut errs);
+
+    if !errs.is_empty() {
+        return CommonError::new(&CommonDefaultErrorKind::ExecuteFail,
+            format!("{} values in {} failed to rotate, file not written : {}", errs.len(), path, errs.join(", "))).to_result();
+    }
+    Ok((doc.to_string(), cnt))
+}
+
+fn rotate_file(old : &'_ SecretKey, new : &'_ SecretKey, path : &'_ str) -> Result<usize, CommonError> {
+    let data = std::fs::read_to_string(path).map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("read {} failed : {}", path, e))
+    })?;
+
+    let (ret, cnt) = rotate_toml(old, new, path, data.as_str())?;
+
+    let tmp = Path::new(path).with_extension("tmp");
+    std::fs::write(tmp.as_path(), ret).and_then(|_| {
+        std::fs::rename(tmp.as_path(), path)
+    }).map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("write {} failed : {}", path, e))
+    })?;
+    Ok(cnt)
+}
+
+fn rotate_db(old : &'_ SecretKey, new : &'_ SecretKey, base_dir : &'_ str, identifier : &'_ str) -> Result<usize, CommonError> {
+    let config_dir = Path::new(base_dir).join("config").join(identifier).to_string_lossy().to_string();
+    let loader = PairDbLoader::new(identifier.to_string(), config_dir.as_str(), true, false, Some(old.clone()))?;
+    loader.rotate_conn_passwd(&|x| rotate_value(old, new, x))
+}
+
+pub(crate) fn run_secret(cmd : SecretCommand) -> Result<(), CommonError> {
+    match cmd {
+        SecretCommand::Encrypt { key_file, value } => {
+            let key = match Path::new(key_file.as_str()).exists() {
+                true => SecretKey::load(key_file.as_str())?,
+                false => {
+                    let key = SecretKey::generate(key_file.as_str())?;
+                    eprintln!("secret key created : {}", key_file);
+                    key
+                }
+            };
+
+            for v in value {
+                println!("{}", key.encrypt(v.as_str())?);
+            }
+        },
+        SecretCommand::Rotate { key_file, new_key_file, value, file, db_base_dir, db_identifier } => {
+            if value.is_empty() && file.is_none() && db_base_dir.is_none() {
+                return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, "rotate need --value, --file or --db-base-dir").to_result();
+            }
+
+            let old = SecretKey::load(key_file.as_str())?;
+            let new = match Path::new(new_key_file.as_str()).exists() {
+                true => SecretKey::load(new_key_file.as_str())?,
+                false => {
+                    let key = SecretKey::generate(new_key_file.as_str())?;
+                    eprintln!("secret key created : {}", new_key_file);
+                    key
+                }
+            };
+
+            for v in value {
+                println!("{}", rotate_value(&old, &new, v.as_str())?);
+            }
+            if let (Some(base_dir), Some(identifier)) = (db_base_dir, db_identifier) {
+                let cnt = rotate_db(&old, &new, base_dir.as_str(), identifier.as_str())?;
+                eprintln!("rotated {} conn_passwd rows of {}", cnt, identifier);
+            }
+            if let Some(path) = file {
+                let cnt = rotate_file(&old, &new, path.as_str())?;
+                eprintln!("rotated {} values in {}", cnt, path);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_key(name : &'_ str) -> SecretKey {
+        let path = std::env::temp_dir().join(format!("mypip_secret_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_file(path.as_path());
+        let key = SecretKey::generate(path.to_string_lossy().as_ref()).expect("generate key failed");
+        let _ = std::fs::remove_file(path.as_path());
+        key
+    }
+
+    #[test]
+    fn rotate_toml_only_string_values() {
+        let old = create_key("old");
+        let new = create_key("new");
+        let enc = old.encrypt("passwd").unwrap();
+        let data = format!("# enc:abc in comment\nconn_passwd = \"{}\" # keep\nnote = 'plain'\n\n[pg]\nodbc = {{ pwd = '{}' }}\n", enc, enc);
+
+        let (ret, cnt) = rotate_toml(&old, &new, "test.toml", data.as_str()).unwrap();
+        assert_eq!(cnt, 2);
+        assert!(ret.starts_with("# enc:abc in comment\nconn_passwd = \""));
+        assert!(ret.contains("\" # keep\nnote = 'plain'\n\n[pg]\n"));
+
+        let doc = ret.parse::<toml_edit::DocumentMut>().unwrap();
+        let rotated = doc["conn_passwd"].as_str().unwrap();
+        assert_ne!(rotated, enc.as_str());
+        assert_eq!(new.decrypt(rotated).unwrap(), "passwd");
+        assert_eq!(new.decrypt(doc["pg"]["odbc"]["pwd"].as_str().unwrap()).unwrap(), "passwd");
+    }
+
+    #[test]
+    fn rotate_file_not_written_on_failure() {
+        let old = create_key("old_fail");
+        let new = create_key("new_fail");
+        let enc = old.encrypt("passwd").unwrap();
+        let data = format!("conn_passwd = \"{}\"\nbad = \"enc:!!\"\n", enc);
+
+        let err = rotate_toml(&old, &new, "test.toml", data.as_str()).unwrap_err();
+        assert!(err.to_string().contains("bad"));
+
+        let path = std::env::temp_dir().join(format!("mypip_secret_{}_rotate.toml", std::process::id()));
+        std::fs::write(path.as_path(), data.as_str()).unwrap();
+        assert!(rotate_file(&old, &new, path.to_string_lossy().as_ref()).is_err());
+        assert_eq!(std::fs::read_to_string(path.as_path()).unwrap(), data);
+        let _ = std::fs::remove_file(path.as_path());
+    }
+}
@@ -23,6 +23,8 @@ fn test_main() -> Result<(), Box<dyn std::error::Error>> {
         },
         script_lib: None,
         db_config: None,
+        secret_key_file: None,
+        interpreter_conf: None,
     })?;
 
     let mut cancel = PlanThreadExecutor::daemon();
@@ -2,4 +2,41 @@ pub const PLAN_TYPE_SCRIPT : &'static str = "script";
 pub const PLAN_TYPE_QUERY : &'static str = "query";
 
 pub const CONVERT_HARD_BIND_PARAM_PREFIX : &'static str = "$$CONV_BIND_PARAM:";
-pub const CONVERT_SQL_BIND_PARAM_PREFIX : &'static str = "$$BIND_PARAM:";
\ No newline at end of file
+pub const CONVERT_SQL_BIND_PARAM_PREFIX : &'static str = "$$BIND_PARAM:";
+
+pub const CHAIN_MODE_ROW : &'static str = "row";
+pub const CHAIN_MODE_BATCH : &'static str = "batch";
+pub const CHAIN_DEFAULT_BATCH_SIZE : usize = 500;
+pub const CHAIN_SCYLLA_DEFAULT_BATCH_SIZE : usize = 20;
+pub const PG_MAX_BIND_PARAM : usize = 65535;
+pub const CHAIN_MERGE_VALUE_COL : &'static str = "value";
+pub const CHAIN_PLAN_TX_GROUP : &'static str = "$$PLAN_TX";
+
+pub const CHAIN_KIND_QUERY : &'static str = "query";
+pub const CHAIN_KIND_SCRIPT : &'static str = "script";
+pub const CHAIN_SCRIPT_DEFAULT_FUNC : &'static str = "transform";
+
+pub const BIND_MODE_ZIP : &'static str = "zip";
+pub const BIND_MODE_CROSS : &'static str = "cross";
+pub const BIND_MODE_BROADCAST : &'static str = "broadcast";
+pub const BIND_MODE_LOOKUP : &'static str = "lookup";
+
+pub const CHAIN_KIND_SINK : &'static str = "sink";
+pub const CHAIN_KIND_ASSERT : &'static str = "assert";
+pub const SINK_FORMAT_CSV : &'static str = "csv";
+pub const SINK_FORMAT_JSONL : &'static str = "jsonl";
+
+pub const LAST_SUCCESS_STORE_SUFFIX : &'static str = ".last_success_time";
+
+pub const PAGINATE_MODE_KEYSET : &'static str = "keyset";
+pub const PAGINATE_MODE_OFFSET : &'static str = "offset";
+pub const PAGINATE_ARG_LIMIT : &'static str = "page_limit";
+pub const PAGINATE_ARG_OFFSET : &'static str = "page_offset";
+pub const PAGINATE_ARG_KEY : &'static str = "page_key";
+
+pub const CHAIN_KIND_REDIS : &'static str = "redis";
+pub const REDIS_DEFAULT_PIPELINE : usize = 100;
+
+pub const ROW_ERROR_ABORT : &'static str = "abort";
+pub const ROW_ERROR_SKIP : &'static str = "skip";
+pub const ROW_ERROR_DEAD_LETTER : &'static str = "dead_letter";
@@ -5,21 +5,25 @@ use common_rs::c_core::func;
 use common_rs::c_err::CommonError;
 use common_rs::c_err::gen::CommonDefaultErrorKind;
 use common_rs::log_trace;
-use common_rs::logger::{log_debug, log_error};
+use common_rs::logger::{log_debug, log_error, log_info};
 use mypip_global::GLOBAL;
 use query::QueryEntry;
-use crate::types::PlanThreadStateRunSet;
-use mypip_types::config::plan::{Plan, PlanInterval};
+use crate::types::{PlanThreadStateRunSet, QueryRunContext};
+use mypip_types::config::plan::{Plan, PlanInterval, PlanWatermark};
 use mypip_types::interface::GlobalLayout;
 use crate::entry::script::ScriptEntry;
 
 mod query;
 mod script;
+mod watermark;
 
 pub(super) struct PlanThreadEntry {
     name : String,
     plan : Plan,
     run_state : Arc<PlanThreadStateRunSet>,
+    run_seq : u64,
+    last_success_time : Option<u128>,
+    last_success_loaded : bool,
 
     signal   : Arc<crate::types::PlanThreadSignal>
 }
@@ -31,7 +35,7 @@ fn get_plan_next_sleep_time_millie(conn_name_opt : Option<&String>) -> Result<u1
         })?.as_millis())
     } else {
         let conn_name = conn_name_opt.unwrap();
-        if conn_name == "" {
+        if conn_name == "" || GLOBAL.is_conn_open_circuit(conn_name.into())? {
             let since = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_err(|e| {
                 CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
             })?;
@@ -39,24 +43,46 @@ fn get_plan_next_sleep_time_millie(conn_name_opt : Option<&String>) -> Result<u1
             return Ok(since.as_millis());
         }
 
-        let p = GLOBAL.get_exec_pool(conn_name.into()).map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::Etc, "failed get pool", e)
-        })?;
+        let need_check = GLOBAL.acquire_conn_slot(conn_name.into())?;
+        let ret = get_conn_current_time_millie(conn_name.as_str(), need_check);
+        GLOBAL.release_conn_slot(conn_name.into())?;
+        ret
+    }
+}
 
+fn get_conn_current_time_millie(conn_name : &'_ str, need_check : bool) -> Result<u128, CommonError> {
+    let p = GLOBAL.get_exec_pool(conn_name.into()).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::Etc, "failed get pool", e)
+    })?;
+
+    let mut try_cnt = if need_check { 2 } else { 1 };
+    loop {
         let mut item = p.get_owned(()).map_err(|e| {
+            let _ = GLOBAL.report_conn_result(conn_name.into(), false);
             CommonError::extend(&CommonDefaultErrorKind::Etc, "failed get item", e)
         })?;
 
-        let conn = item.get_value();
-        let current = conn.get_current_time().map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "failed get current time", e)
-        })?;
-
-        Ok(current.as_millis())
+        try_cnt -= 1;
+        match item.get_value().get_current_time() {
+            Ok(current) => {
+                item.restoration();
+                let _ = GLOBAL.report_conn_result(conn_name.into(), true);
+                return Ok(current.as_millis());
+            },
+            Err(e) if try_cnt > 0 => {
+                log_debug!(conn_name, "idle connection check failed, retry new item, {}", e);
+                item.dispose();
+            },
+            Err(e) => {
+                item.dispose();
+                let _ = GLOBAL.report_conn_result(conn_name.into(), false);
+                return CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "failed get current time", e).to_result();
+            }
+        }
     }
 }
 
-fn plan_thread_sleep(interval : &PlanInterval) -> Result<(), CommonError> {
+fn plan_thread_sleep(interval : &PlanInterval) -> Result<u128, CommonError> {
     let millie = get_plan_next_sleep_time_millie(interval.connection.as_ref()).map_err(|e| {
         CommonError::extend(&CommonDefaultErrorKind::Etc, "failed get current interval", e)
     });
@@ -70,10 +96,10 @@ fn plan_thread_sleep(interval : &PlanInterval) -> Result<(), CommonError> {
         std::thread::sleep(Duration::from_millis(epel as u64 + 10));
     }
 
-    Ok(())
+    Ok(millie_ok + epel)
 }
 
-pub fn plan_thread_fn(entry : PlanThreadEntry) {
+pub fn plan_thread_fn(mut entry : PlanThreadEntry) {
     let sig = entry.signal.clone();
     log_debug!(entry.name.as_str(), "starting plan thread");
 
@@ -90,14 +116,17 @@ pub fn plan_thread_fn(entry : PlanThreadEntry) {
         }
 
         let sleep = plan_thread_sleep(&entry.plan.interval);
-        if sleep.is_err() {
-            log_error!(entry.name.as_str(), "{}", sleep.err().unwrap());
-            break;
-        }
+        let scheduled_time = match sleep {
+            Ok(t) => t,
+            Err(e) => {
+                log_error!(entry.name.as_str(), "{}", e);
+                break;
+            }
+        };
         log_debug!(entry.name.as_str(), "entry start");
 
         let start = SystemTime::now();
-        let entry_ret =  entry.run();
+        let entry_ret =  entry.run(scheduled_time);
 
         let epel = SystemTime::now().duration_since(start).map_err(|e| {
             CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
@@ -123,14 +152,75 @@ impl PlanThreadEntry {
             name,
             plan,
             run_state,
+            run_seq : 0,
+            last_success_time : None,
+            last_success_loaded : false,
             signal,
         }
     }
-    fn run(&self) -> Result<(), CommonError> {
-        match self.plan.type_name.as_str() {
+    fn find_open_circuit(&self) -> Result<Option<String>, CommonError> {
+        let mut conns : Vec<&String> = self.plan.interval.connection.iter().collect();
+        for item in self.plan.chain.iter().flatten() {
+            conns.push(&item.connection);
+            if let Some(conn) = item.dead_letter.as_ref().and_then(|x| x.connection.as_ref()) {
+                conns.push(conn);
+            }
+        }
+
+        for conn in conns.into_iter().filter(|x| !x.is_empty()) {
+            if GLOBAL.is_conn_open_circuit(conn.into())? {
+                return Ok(Some(conn.clone()));
+            }
+        }
+        Ok(None)
+    }
+    fn run(&mut self, scheduled_time : u128) -> Result<(), CommonError> {
+        if let Some(conn) = self.find_open_circuit()? {
+            log_info!(self.name.as_str(), "skip plan, connection {} open-circuit", conn);
+            return Ok(());
+        }
+
+        if !self.last_success_loaded {
+            self.last_success_time = self.load_last_success_time()?;
+            self.last_success_loaded = true;
+        }
+
+        self.run_seq += 1;
+        let ret = match self.plan.type_name.as_str() {
             crate::constant::PLAN_TYPE_SCRIPT => self.run_script(),
-            _ => self.run_query()
+            _ => self.run_query(scheduled_time)
+        };
+
+        if ret.is_ok() {
+            self.last_success_time = Some(scheduled_time);
+            if let Some((key, store)) = self.get_last_success_store() {
+                if let Err(e) = GLOBAL.save_watermark(key.as_str(), &store, scheduled_time.to_string().as_str()) {
+                    log_error!(self.name.as_str(), "save last success time failed, {}", e);
+                }
+            }
         }
+        ret
+    }
+    // last_success_time is kept next to the watermark, plans without one keep it in memory only
+    fn get_last_success_store(&self) -> Option<(String, PlanWatermark)> {
+        self.plan.watermark.as_ref().map(|wm| {
+            let mut store = wm.clone();
+            store.path = wm.path.as_ref().map(|x| format!("{}{}", x, crate::constant::LAST_SUCCESS_STORE_SUFFIX));
+            (format!("{}{}", self.name, crate::constant::LAST_SUCCESS_STORE_SUFFIX), store)
+        })
+    }
+    fn load_last_success_time(&self) -> Result<Option<u128>, CommonError> {
+        let (key, store) = match self.get_last_success_store() {
+            Some(x) => x,
+            None => return Ok(None)
+        };
+
+        let data = GLOBAL.load_watermark(key.as_str(), &store).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "load last success time failed", e)
+        })?;
+        data.map(|x| x.parse::<u128>().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("last success time {} parse failed, {}", x, e))
+        })).transpose()
     }
     fn run_script(&self) -> Result<(), CommonError> {
         let info = self.plan.script.clone().map_or(Err(
@@ -146,14 +236,37 @@ impl PlanThreadEntry {
         Ok(())
     }
 
-    fn run_query(&self) -> Result<(), CommonError> {
+    fn run_query(&self, scheduled_time : u128) -> Result<(), CommonError> {
         let info = self.plan.chain.clone().map_or(Err(
             CommonError::new(&CommonDefaultErrorKind::NoData, "not exists data")
         ), |x| {
             Ok(x)
         })?;
 
-        let exec = QueryEntry::new(self.name.as_str(), info.as_slice());
-        exec.run()
+        let watermark = match self.plan.watermark.as_ref() {
+            Some(wm) => GLOBAL.load_watermark(self.name.as_str(), wm).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "load watermark failed", e)
+            })?.or_else(|| wm.initial.clone()),
+            None => None
+        };
+
+        let ctx = QueryRunContext {
+            plan_name : self.name.clone(),
+            run_id : format!("{}-{}-{}", self.name, scheduled_time, self.run_seq),
+            scheduled_time,
+            last_success_time : self.last_success_time,
+            watermark,
+        };
+
+        let exec = QueryEntry::new(&ctx, info.as_slice(), self.plan.transaction, self.plan.watermark.as_ref());
+        let next = exec.run()?;
+
+        if let (Some(wm), Some(next)) = (self.plan.watermark.as_ref(), next) {
+            GLOBAL.save_watermark(self.name.as_str(), wm, next.as_str()).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "save watermark failed", e)
+            })?;
+            log_debug!(self.name.as_str(), "watermark advanced {}", next);
+        }
+        Ok(())
     }
 }
\ No newline at end of file
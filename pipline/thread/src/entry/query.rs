@@ -1,39 +1,88 @@
-use std::cell::{RefCell};
 use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::sync::Mutex;
 use common_rs::c_err::CommonError;
 use common_rs::c_err::gen::CommonDefaultErrorKind;
 use common_rs::exec::interfaces::pair::*;
 use mypip_global::GLOBAL;
-use mypip_types::config::plan::PlanChain;
+use mypip_types::config::plan::{PlanChain, PlanWatermark};
 use mypip_types::interface::GlobalLayout;
 use common_rs::logger::*;
+use when::WhenEvaluator;
+use merge::BindResultMerger;
+use placeholder::ChainQuery;
+use tx::TxQuery;
+use dag::ChainDag;
+use bind_mode::BindRow;
+use paginate::Paginator;
+use row_error::RowErrorHandler;
+use crate::types::QueryRunContext;
+use super::watermark::WatermarkMax;
+
+mod when;
+mod batch;
+mod merge;
+mod placeholder;
+mod value;
+mod tx;
+mod transform;
+mod dag;
+mod bind_mode;
+mod sink;
+mod assertion;
+mod paginate;
+mod redis_cmd;
+mod cast;
+mod row_error;
+
+type ChainRunner<'r> = dyn FnMut(&PlanChain, &str, Vec<Vec<PairValueEnum>>) -> Result<Vec<PairValueEnum>, CommonError> + 'r;
+
+pub(super) struct ConnSlot {
+    name : String,
+    need_check : bool,
+}
+
+impl ConnSlot {
+    pub fn acquire(name : &'_ str) -> Result<Self, CommonError> {
+        let need_check = GLOBAL.acquire_conn_slot(name.into())?;
+        Ok(ConnSlot { name : name.to_string(), need_check })
+    }
+}
+
+impl Drop for ConnSlot {
+    fn drop(&mut self) {
+        if let Err(e) = GLOBAL.release_conn_slot(self.name.as_str().into()) {
+            log_error!(self.name.as_str(), "release connection slot failed, {}", e);
+        }
+    }
+}
 
 #[derive(Default)]
 struct QueryEntryCache<'a> {
     bind_data_map: HashMap<&'a str, usize>,
+    page : Option<usize>,
 }
 
 impl<'a> QueryEntryCache<'a> {
     pub fn put(&mut self, key: &'a str, value : usize) {
         self.bind_data_map.insert(key, value);
     }
-    pub fn get_max_cnt(&self) -> usize {
-        let m = self.bind_data_map.iter().max_by(|x, x1| {
-            x.1.cmp(x1.1)
-        });
-
-        m.expect(format!("{} - get max is broken", common_rs::c_core::utils::macros::func!()).as_str()).1.clone()
+    pub fn get_cnts<'b>(&self, bind_ids : &'_ [&'b str]) -> Vec<(&'b str, usize)> {
+        bind_ids.iter().map(|id| (*id, self.bind_data_map.get(id).copied().unwrap_or(0))).collect()
     }
 }
 
 pub(crate) struct QueryEntry<'a> {
     plan_name : &'a str,
     chain : &'a [PlanChain],
+    ctx : &'a QueryRunContext,
+    transaction : bool,
+    watermark : Option<&'a PlanWatermark>,
 
-    cache : RefCell<QueryEntryCache<'a>>
+    cache : Mutex<QueryEntryCache<'a>>
 }
 
-fn create_query_bind_from_args_array(v: &mut Vec<PairValueEnum>, p : &'_ PlanChain)  -> Result<(), CommonError> {
+fn create_query_bind_from_args_array(v: &mut Vec<PairValueEnum>, p : &'_ PlanChain, ctx : &'_ QueryRunContext)  -> Result<(), CommonError> {
     if let Some(args) = p.args.as_ref() {
         if args.len() <= 0 {
             return Ok(());
@@ -42,18 +91,20 @@ fn create_query_bind_from_args_array(v: &mut Vec<PairValueEnum>, p : &'_ PlanCha
         if args.len() > v.len() {
             v.resize(args.len(), PairValueEnum::Null);
         }
-        for arg in args.iter() {
+        for arg in args.iter().filter(|x| x.name.is_none()) {
             if arg.idx <= 0 {
                 return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} Index out of bounds: {}", p.id, arg.idx)).to_result();
             }
 
-            v[arg.idx - 1] = PairValueEnum::String(arg.data.clone());
+            v[arg.idx - 1] = value::create_arg_value(arg, ctx).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ParsingFail, format!("{} arg {} value failed", p.id, arg.idx), e)
+            })?;
         }
     }
     Ok(())
 }
 
-fn create_query_bind_from_bind_array(v: &mut Vec<PairValueEnum>, p : &'_ PlanChain, m : &HashMap<String, PairValueEnum>, use_idx : usize) -> Result<(), CommonError> {
+fn create_query_bind_from_bind_array(v: &mut Vec<PairValueEnum>, p : &'_ PlanChain, m : &HashMap<String, PairValueEnum>, bind_row : &'_ BindRow) -> Result<(), CommonError> {
     if let Some(binds) = p.bind.as_ref() {
         if binds.len() <= 0 {
             return Ok(());
@@ -81,7 +132,7 @@ fn create_query_bind_from_bind_array(v: &mut Vec<PairValueEnum>, p : &'_ PlanCha
             }
         }
 
-        for bind in binds {
+        for bind in binds.iter().filter(|x| x.name.is_none()) {
             if bind.idx <= 0 {
                 return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} Index out of bounds: {}", p.id, bind.idx)).to_result();
             }
@@ -92,13 +143,14 @@ fn create_query_bind_from_bind_array(v: &mut Vec<PairValueEnum>, p : &'_ PlanCha
 
             if let Some(PairValueEnum::Map(ele)) = m.get(bind.id.as_str()) {
                 if let Some(PairValueEnum::Array(cols)) = ele.get(bind.key.as_str()) {
-                    if let Some(row_idx) = bind.row {
-                        let data = cols.get(row_idx).map_or(PairValueEnum::Null, |col| {col.clone()});
-                        v[bind.idx-1] = data;
-                    } else {
-                        let data = cols.get(use_idx).map_or(PairValueEnum::Null, |col| {col.clone()});
-                        v[bind.idx-1] = data;
-                    }
+                    let row_idx = bind.row.unwrap_or_else(|| bind_row.get(bind.id.as_str()));
+                    let data = cols.get(row_idx).map_or(PairValueEnum::Null, |col| {col.clone()});
+                    v[bind.idx-1] = match bind.cast.as_ref() {
+                        Some(c) => cast::cast_value(data, c.as_str()).map_err(|e| {
+                            CommonError::extend(&CommonDefaultErrorKind::NotMatchArgs, format!("{} bind {}.{} row {} cast failed", p.id, bind.id, bind.key, row_idx), e)
+                        })?,
+                        None => data
+                    };
                 }
             } else {
                 return CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} not exists bind id data {}", p.id, bind.idx)).to_result();
@@ -108,26 +160,26 @@ fn create_query_bind_from_bind_array(v: &mut Vec<PairValueEnum>, p : &'_ PlanCha
     Ok(())
 }
 
-fn create_query_bind_array(p : &'_ PlanChain, m : &HashMap<String, PairValueEnum>, use_bind_idx : usize) -> Result<Vec<PairValueEnum>, CommonError> {
+fn create_query_bind_array(p : &'_ PlanChain, m : &HashMap<String, PairValueEnum>, bind_row : &'_ BindRow, ctx : &'_ QueryRunContext) -> Result<Vec<PairValueEnum>, CommonError> {
     let mut v = Vec::new();
-    create_query_bind_from_args_array(&mut v, p).map_err(|e| {
+    create_query_bind_from_args_array(&mut v, p, ctx).map_err(|e| {
         CommonError::extend(&CommonDefaultErrorKind::ParsingFail, "args array use failed", e)
     })?;
-    create_query_bind_from_bind_array(&mut v, p, m, use_bind_idx).map_err(|e| {
+    create_query_bind_from_bind_array(&mut v, p, m, bind_row).map_err(|e| {
         CommonError::extend(&CommonDefaultErrorKind::ParsingFail, "bind array use failed", e)
     })?;
     Ok(v)
 }
 
 fn chk_conflict_bind_param(p : &'_[PlanChain]) -> bool {
-    let mut exists_set = HashSet::<usize>::new();
+    let mut exists_set = HashSet::<String>::new();
 
     for plan in p {
         exists_set.clear();
         
         if let Some(args) = plan.args.as_ref() {
             for arg in args {
-                if !exists_set.insert(arg.idx) {
+                if !exists_set.insert(arg.name.clone().unwrap_or_else(|| arg.idx.to_string())) {
                     return false;
                 }
             }
@@ -135,7 +187,7 @@ fn chk_conflict_bind_param(p : &'_[PlanChain]) -> bool {
 
         if let Some(binds) = plan.bind.as_ref() {
             for bind in binds {
-                if !exists_set.insert(bind.idx) {
+                if !exists_set.insert(bind.name.clone().unwrap_or_else(|| bind.idx.to_string())) {
                     return false;
                 }
             }
@@ -145,6 +197,17 @@ fn chk_conflict_bind_param(p : &'_[PlanChain]) -> bool {
     true
 }
 
+fn get_bind_value(m : &HashMap<String, PairValueEnum>, id : &'_ str, key : &'_ str, row : usize) -> Result<PairValueEnum, CommonError> {
+    match m.get(id) {
+        Some(PairValueEnum::Map(cols)) => match cols.get(key) {
+            Some(PairValueEnum::Array(arr)) => Ok(arr.get(row).map_or(PairValueEnum::Null, |x| x.clone())),
+            Some(other) => Ok(other.clone()),
+            None => CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} not exists column {}", id, key)).to_result()
+        },
+        _ => CommonError::new(&CommonDefaultErrorKind::NoData, format!("not exists bind id data {}", id)).to_result()
+    }
+}
+
 fn get_ret_bind_param_cnt(ret : &PairValueEnum) -> usize {
     let mut max = 0;
 
@@ -163,22 +226,71 @@ fn get_ret_bind_param_cnt(ret : &PairValueEnum) -> usize {
 
     max
 }
-fn run_one_query(item : &PlanChain, plan_name : &'_ str, bind_data : Vec<PairValueEnum>) -> Result<PairValueEnum, CommonError> {
+fn run_many_query(item : &PlanChain, plan_name : &'_ str, query : &'_ str, bind_rows : Vec<Vec<PairValueEnum>>) -> Result<Vec<PairValueEnum>, CommonError> {
+    let slot = ConnSlot::acquire(item.connection.as_str()).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("acquire connection failed {}:{}",plan_name, item.id), e)
+    })?;
+    exec_many_query(item, plan_name, query, bind_rows, slot.need_check)
+}
+fn exec_many_query(item : &PlanChain, plan_name : &'_ str, query : &'_ str, bind_rows : Vec<Vec<PairValueEnum>>, mut need_check : bool) -> Result<Vec<PairValueEnum>, CommonError> {
     let pool_name = item.connection.as_str();
-    let p = GLOBAL.get_exec_pool(pool_name.into()).map_err(|e| {
-        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get pool failed {}:{}",plan_name, item.id), e)
+    let read_only = item.read_only.unwrap_or(false);
+    let conn_info = GLOBAL.get_conn_info(pool_name.into()).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get conn info failed {}:{}",plan_name, item.id), e)
     })?;
+    let max_try = conn_info.conn_addr.len() + conn_info.topology.as_ref().and_then(|x| x.replicas.as_ref()).map_or(0, |x| x.len());
+
+    let mut try_cnt = 0;
+    let mut p_item = loop {
+        let p = match read_only {
+            true => GLOBAL.get_read_exec_pool(pool_name.into()),
+            false => GLOBAL.get_exec_pool(pool_name.into())
+        }.map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get pool failed {}:{}",plan_name, item.id), e)
+        })?;
 
-    let mut p_item = p.get_owned(()).map_err(|e| {
-        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get pool item failed {}:{}",plan_name, item.id), e)
-    })?;
+        match p.get_owned(()) {
+            Ok(mut p_item) => {
+                if need_check {
+                    need_check = false;
+                    if let Err(e) = p_item.get_value().get_current_time() {
+                        log_debug!(plan_name, "idle connection check failed {}:{}, retry new item, {}", plan_name, item.id, e);
+                        p_item.dispose();
+                        continue;
+                    }
+                }
+                break p_item
+            },
+            Err(e) => {
+                let _ = GLOBAL.report_conn_result(pool_name.into(), false);
+                try_cnt += 1;
+                if try_cnt >= max_try || !GLOBAL.failover_exec_pool(pool_name.into(), read_only)? {
+                    return CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get pool item failed {}:{}",plan_name, item.id), e).to_result();
+                }
+                log_error!(plan_name, "get pool item failed {}:{}, failover next addr, {}", plan_name, item.id, e);
+            }
+        }
+    };
 
     let conn = p_item.get_value();
+    let mut ret = Ok(Vec::with_capacity(bind_rows.len()));
 
-    let ret = conn.execute_pair(item.query.as_str(), &PairValueEnum::Array(bind_data)).map_err(|e| {
-        CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("query run failed {}:{}",plan_name, item.id), e)
-    });
+    for bind_data in bind_rows {
+        let one = conn.execute_pair(query, &PairValueEnum::Array(bind_data)).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("query run failed {}:{}",plan_name, item.id), e)
+        });
+
+        match one {
+            Ok(data) => ret.as_mut().expect("run many ret is broken").push(data),
+            Err(e) => {
+                ret = Err(e);
+                break;
+            }
+        }
+    }
 
+    // execute failures count toward the circuit breaker the same as checkout failures, a success resets it
+    let _ = GLOBAL.report_conn_result(pool_name.into(), ret.is_ok());
     if ret.is_err() {
         p_item.dispose();
         ret
@@ -189,64 +301,410 @@ fn run_one_query(item : &PlanChain, plan_name : &'_ str, bind_data : Vec<PairVal
 }
 
 impl<'a> QueryEntry<'a> {
-    pub fn run(&self) -> Result<(), CommonError> {
-        let mut data_map = HashMap::<String, PairValueEnum>::new();
-        
-        if self.chain.len() <= 0 {
-            return Ok(());
+    fn is_skip_chain(&self, item : &'_ PlanChain, data_map : &HashMap<String, PairValueEnum>) -> Result<bool, CommonError> {
+        if let Some(when) = item.when.as_ref() {
+            let (is_run, reason) = WhenEvaluator::eval(when.as_str(), data_map).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ParsingFail, format!("when eval failed {}:{}", self.plan_name, item.id), e)
+            })?;
+
+            if !is_run {
+                log_info!(self.plan_name, "skip chain {}, when `{}` is false [{}]", item.id, when, reason);
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn get_tx_group<'b>(&self, item : &'b PlanChain) -> Option<&'b str> {
+        if self.transaction {
+            Some(crate::constant::CHAIN_PLAN_TX_GROUP)
+        } else {
+            item.tx_group.as_deref()
         }
+    }
 
-        if !chk_conflict_bind_param(self.chain) {
-            return CommonError::new(&CommonDefaultErrorKind::Etc, "conflict idx bind or args").to_result();
+    fn run_chain(&self, item : &'_ PlanChain, data_map : &HashMap<String, PairValueEnum>, runner : &mut ChainRunner<'_>) -> Result<PairValueEnum, CommonError> {
+        match item.kind.as_ref().map(|x| x.as_str()) {
+            None | Some(crate::constant::CHAIN_KIND_QUERY) => {},
+            Some(crate::constant::CHAIN_KIND_SCRIPT) => return transform::run_script_chain(item, self.plan_name, data_map),
+            Some(crate::constant::CHAIN_KIND_SINK) => {
+                let page = self.cache.lock().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+                })?.page;
+                return sink::run_sink_chain(item, self.ctx, page, data_map);
+            },
+            Some(crate::constant::CHAIN_KIND_ASSERT) => return assertion::run_assert_chain(item, self.ctx, data_map),
+            Some(crate::constant::CHAIN_KIND_REDIS) => return redis_cmd::run_redis_chain(item, self.ctx, data_map, self.get_tx_group(item).is_some(), runner),
+            Some(other) => return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} not support kind {}", item.id, other)).to_result()
         }
 
-        let mut bind_data = Vec::new();
-        create_query_bind_from_args_array(&mut bind_data, &self.chain[0]).map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("bind data create failed {}",self.plan_name), e)
+        let chain_query = ChainQuery::new(item, self.ctx)?;
+        let bind_ids = chain_query.bind_ids(item);
+
+        if bind_ids.len() <= 0 {
+            let bind_data = chain_query.create_bind(item, data_map, &BindRow::default()).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("bind data create failed {}:{}",self.plan_name, item.id), e)
+            })?;
+
+            let mut ret = runner(item, chain_query.query.as_str(), vec![bind_data])?;
+            return Ok(ret.pop().unwrap_or(PairValueEnum::Null));
+        }
+
+        let bind_cnts = self.cache.lock().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?.get_cnts(bind_ids.as_slice());
+        let bind_rows = bind_mode::create_bind_rows(item, bind_cnts.as_slice(), data_map).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("bind rows create failed {}:{}", self.plan_name, item.id), e)
         })?;
+        let mut row_err = RowErrorHandler::new(item, self.ctx, self.get_tx_group(item).is_some())?;
+        if batch::is_batch_mode(item)? {
+            let ret = batch::run_batch_chain(item, &chain_query, self.plan_name, data_map, bind_rows.as_slice(), &mut row_err, runner);
+            row_err.finish();
+            return ret;
+        }
+
+        let mut merger = BindResultMerger::default();
+        for bind_row in bind_rows.iter() {
+            let bind_data = match chain_query.create_bind(item, data_map, bind_row) {
+                Ok(d) => d,
+                Err(e) => {
+                    row_err.handle(&[], CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("query bind param set failed {} row {}", self.plan_name, bind_row.idx), e))?;
+                    continue;
+                }
+            };
+            let keep = if row_err.is_abort() { Vec::new() } else { bind_data.clone() };
+
+            match runner(item, chain_query.query.as_str(), vec![bind_data]) {
+                Ok(bind_ret) => {
+                    row_err.success(1);
+                    for ret in bind_ret {
+                        merger.push(ret, item.source_row_col.as_ref().map(|col| (col.as_str(), bind_row.idx)))?;
+                    }
+                },
+                Err(e) => {
+                    row_err.handle(keep.as_slice(), CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("query bind param run {} row {}", self.plan_name, bind_row.idx), e))?;
+                }
+            }
+        }
+        row_err.finish();
+        Ok(merger.finish())
+    }
+
+    fn run_step(&self, item : &'_ PlanChain, data_map : &HashMap<String, PairValueEnum>, runner : &mut ChainRunner<'_>) -> Result<Option<PairValueEnum>, CommonError> {
+        if self.is_skip_chain(item, data_map)? {
+            return Ok(None);
+        }
+        log_debug!(self.plan_name, "try running, query={}", item.query.as_str());
 
-        let first_data = run_one_query(&self.chain[0], self.plan_name, bind_data).map_err(|e| {
-            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "first query run failed", e)
+        let ret = self.run_chain(item, data_map, runner).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("chain run failed {}:{}", self.plan_name, item.id), e)
         })?;
+        Ok(Some(ret))
+    }
 
-        self.cache.borrow_mut().put(&self.chain[0].id, get_ret_bind_param_cnt(&first_data));
+    fn put_result(&self, item : &'a PlanChain, ret : PairValueEnum, data_map : &mut HashMap<String, PairValueEnum>) -> Result<(), CommonError> {
+        self.cache.lock().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+        })?.put(&item.id, get_ret_bind_param_cnt(&ret));
+        data_map.insert(item.id.clone(), ret);
 
-        data_map.insert(self.chain[0].id.clone(), first_data);
+        log_debug!(self.plan_name, "try running, name={} success", item.id.as_str());
+        Ok(())
+    }
 
-        log_debug!(self.plan_name, "try running, name={} first success", self.chain[0].id.as_str());
+    fn run_parallel(&self, steps : &'_ [usize], data_map : &HashMap<String, PairValueEnum>) -> Vec<(usize, Result<Option<PairValueEnum>, CommonError>)> {
+        let run_one = |idx : usize| {
+            self.run_step(&self.chain[idx], data_map, &mut |c, query, rows| {
+                run_many_query(c, self.plan_name, query, rows)
+            })
+        };
 
-        for item in self.chain.iter().skip(1) {
-            log_debug!(self.plan_name, "try running, query={}", item.query.as_str());
+        if steps.len() <= 1 {
+            return steps.iter().map(|idx| (*idx, run_one(*idx))).collect();
+        }
 
-            let ret : PairValueEnum = if let Some(_) = &item.bind {
-                let mut bind_ret = PairValueEnum::Null;
-                for bind_idx in 0..self.cache.borrow_mut().get_max_cnt() {
-                    let bind_data = create_query_bind_array(item, &data_map, bind_idx).map_err(|e| {
-                        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("query bind param set failed {}", self.plan_name), e)
-                    })?;
-                    bind_ret = run_one_query(item, &self.plan_name, bind_data).map_err(|e| {
-                        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("query bind param run {}", self.plan_name), e)
-                    })?;
+        let run_one = &run_one;
+        log_debug!(self.plan_name, "parallel running, count={}", steps.len());
+
+        std::thread::scope(|s| {
+            let handles : Vec<_> = steps.iter().map(|idx| {
+                let idx = *idx;
+                (idx, s.spawn(move || run_one(idx)))
+            }).collect();
+
+            handles.into_iter().map(|(idx, h)| {
+                (idx, h.join().unwrap_or_else(|_| {
+                    CommonError::new(&CommonDefaultErrorKind::Critical, format!("chain thread panic {}:{}", self.plan_name, self.chain[idx].id)).to_result()
+                }))
+            }).collect()
+        })
+    }
+
+    fn run_dag(&self, dag : &'_ ChainDag, done : &'_ HashSet<usize>, mut data_map : HashMap<String, PairValueEnum>) -> Result<HashMap<String, PairValueEnum>, CommonError> {
+        // keyed by (tx_group, connection), a group stays open until its last member in run order finishes
+        let mut pinned : HashMap<(String, String), (String, _, ConnSlot)> = HashMap::new();
+
+        macro_rules! end_tx {
+            ($pinned:ident, $filter:expr, $is_commit:expr) => {{
+                let keys : Vec<(String, String)> = $pinned.keys().filter($filter).cloned().collect();
+                let mut is_commit : bool = $is_commit;
+                let mut end_ret : Result<(), CommonError> = Ok(());
+
+                for key in keys {
+                    let Some((conn_type, mut p_item, slot)) = $pinned.remove(&key) else {
+                        continue;
+                    };
+                    let q = if is_commit { TxQuery::Commit } else { TxQuery::Rollback };
+                    let end = tx::get_tx_query(conn_type.as_str(), q).and_then(|q| {
+                        p_item.get_value().execute_pair(q, &PairValueEnum::Array(Vec::new()))
+                    });
+
+                    match end {
+                        Ok(_) => p_item.restoration(),
+                        Err(e) => {
+                            p_item.dispose();
+                            if end_ret.is_ok() {
+                                end_ret = Err(CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("transaction end failed {}:{}", self.plan_name, key.1), e));
+                            }
+                            is_commit = false;
+                        }
+                    }
+                    drop(p_item);
+                    drop(slot);
+                    log_debug!(self.plan_name, "transaction end, group={} connection={} commit={}", key.0.as_str(), key.1.as_str(), is_commit);
                 }
-                Ok(bind_ret)
-            } else {
-                let mut bind_data = Vec::new();
-                create_query_bind_from_args_array(&mut bind_data, item).map_err(|e| {
-                    CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("bind data create failed {}:{}",self.plan_name, item.id), e)
-                })?;
+                end_ret
+            }};
+        }
 
-                run_one_query(item, self.plan_name, bind_data).map_err(|e| {
-                    CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, "first query run failed", e)
-                })
-            }?;
+        let mut waves = dag.waves.clone();
+        for wave in waves.iter_mut() {
+            wave.retain(|x| !done.contains(x));
+            wave.sort_by_key(|x| self.get_tx_group(&self.chain[*x]).is_none());
+        }
+        waves.retain(|x| !x.is_empty());
+        let order : Vec<usize> = waves.iter().flatten().copied().collect();
+        let group_ends = dag::find_tx_group_ends(order.as_slice(), |x| self.get_tx_group(&self.chain[x]));
+
+        let mut failed = HashSet::<usize>::new();
+        let mut reports = Vec::<String>::new();
+        let mut first_err : Option<CommonError> = None;
+        let mut abort : Option<CommonError> = None;
+
+        'wave: for wave in waves.iter() {
+            let mut par_steps = Vec::with_capacity(wave.len());
+
+            for idx in wave.iter().copied() {
+                let item = &self.chain[idx];
+                let tx_group = self.get_tx_group(item);
+                let failed_dep = dag.find_failed_dep(idx, &failed);
+
+                if tx_group.is_none() {
+                    if let Some(dep) = failed_dep {
+                        log_error!(self.plan_name, "skip chain {}, dependency {} failed", item.id, self.chain[dep].id);
+                        failed.insert(idx);
+                        reports.push(format!("{}(skipped)", item.id));
+                    } else {
+                        par_steps.push(idx);
+                    }
+                    continue;
+                }
 
-            data_map.insert(item.id.clone(), ret);
+                let step_ret = match failed_dep {
+                    Some(dep) => CommonError::new(&CommonDefaultErrorKind::NoData, format!("skip chain {}, dependency {} failed", item.id, self.chain[dep].id)).to_result(),
+                    None => {
+                        self.run_step(item, &data_map, &mut |c, query, rows| {
+                            if c.read_only.unwrap_or(false) {
+                                return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} read_only not support transaction chain", c.id)).to_result();
+                            }
+                            let key = (tx_group.unwrap_or_default().to_string(), c.connection.clone());
+                            let (_, p_item, _) = match pinned.entry(key) {
+                                Entry::Occupied(e) => e.into_mut(),
+                                Entry::Vacant(e) => {
+                                    let conn_info = GLOBAL.get_conn_info(c.connection.as_str().into()).map_err(|e| {
+                                        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get conn info failed {}:{}", self.plan_name, c.id), e)
+                                    })?;
+                                    let begin = tx::get_tx_query(conn_info.conn_type.as_str(), TxQuery::Begin)?;
+
+                                    let slot = ConnSlot::acquire(c.connection.as_str()).map_err(|e| {
+                                        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("acquire connection failed {}:{}", self.plan_name, c.id), e)
+                                    })?;
+                                    let p = GLOBAL.get_exec_pool(c.connection.as_str().into()).map_err(|e| {
+                                        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get pool failed {}:{}", self.plan_name, c.id), e)
+                                    })?;
+                                    let get_item = || p.get_owned(()).map_err(|e| {
+                                        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get pool item failed {}:{}", self.plan_name, c.id), e)
+                                    });
+                                    let mut p_item = get_item()?;
+                                    if slot.need_check {
+                                        if let Err(check_e) = p_item.get_value().get_current_time() {
+                                            log_debug!(self.plan_name, "idle connection check failed {}:{}, retry new item, {}", self.plan_name, c.id, check_e);
+                                            p_item.dispose();
+                                            p_item = get_item()?;
+                                        }
+                                    }
+
+                                    if let Err(begin_e) = p_item.get_value().execute_pair(begin, &PairValueEnum::Array(Vec::new())) {
+                                        p_item.dispose();
+                                        return CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("transaction begin failed {}:{}", self.plan_name, c.connection), begin_e).to_result();
+                                    }
+                                    log_debug!(self.plan_name, "transaction begin, connection={}", c.connection.as_str());
+                                    e.insert((conn_info.conn_type, p_item, slot))
+                                }
+                            };
+
+                            let conn = p_item.get_value();
+                            let mut ret = Vec::with_capacity(rows.len());
+                            for bind_data in rows {
+                                match conn.execute_pair(query, &PairValueEnum::Array(bind_data)) {
+                                    Ok(one) => ret.push(one),
+                                    Err(e) => {
+                                        let _ = GLOBAL.report_conn_result(c.connection.as_str().into(), false);
+                                        return CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("query run failed {}:{}", self.plan_name, c.id), e).to_result();
+                                    }
+                                }
+                            }
+                            let _ = GLOBAL.report_conn_result(c.connection.as_str().into(), true);
+                            Ok(ret)
+                        })
+                    }
+                };
+
+                let step_ret = step_ret.and_then(|ret| match ret {
+                    Some(ret) => self.put_result(item, ret, &mut data_map),
+                    None => Ok(())
+                });
+
+                // a failed member rolls back every open group, since the plan stops here
+                let end_ret = match &step_ret {
+                    Err(_) => end_tx!(pinned, |_| true, false),
+                    Ok(_) if group_ends.contains(&idx) => end_tx!(pinned, |k| Some(k.0.as_str()) == tx_group, true),
+                    Ok(_) => Ok(())
+                };
+
+                match (step_ret, end_ret) {
+                    (Err(e), Err(end_e)) => {
+                        log_error!(self.plan_name, "{}", end_e);
+                        abort = Some(e);
+                    },
+                    (Err(e), Ok(_)) | (Ok(_), Err(e)) => abort = Some(e),
+                    (Ok(_), Ok(_)) => {}
+                }
+                if abort.is_some() {
+                    break 'wave;
+                }
+            }
+
+            for (idx, step_ret) in self.run_parallel(par_steps.as_slice(), &data_map) {
+                let item = &self.chain[idx];
+                match step_ret {
+                    Ok(Some(ret)) => {
+                        if let Err(e) = self.put_result(item, ret, &mut data_map) {
+                            abort = Some(e);
+                            break 'wave;
+                        }
+                    },
+                    Ok(None) => {},
+                    Err(e) => {
+                        log_error!(self.plan_name, "{}", e);
+                        failed.insert(idx);
+                        reports.push(format!("{}(failed)", item.id));
+                        if first_err.is_none() {
+                            first_err = Some(e);
+                        }
+                    }
+                }
+            }
         }
-        
+
+        if let Err(end_e) = end_tx!(pinned, |_| true, false) {
+            log_error!(self.plan_name, "{}", end_e);
+        }
+        if let Some(e) = abort {
+            return Err(e);
+        }
+
+        if !reports.is_empty() {
+            let msg = format!("chain failed {} [{}]", self.plan_name, reports.join(", "));
+            return match first_err {
+                Some(e) => CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, msg, e).to_result(),
+                None => CommonError::new(&CommonDefaultErrorKind::ExecuteFail, msg).to_result()
+            };
+        }
+
+        Ok(data_map)
+    }
+
+    // each page runs the downstream steps on its own, sinks write one file per page and the watermark keeps the max of every page
+    fn run_paginated(&self, dag : &'_ ChainDag, mut paginator : Paginator<'_>, watermark : &mut Option<WatermarkMax<'_>>) -> Result<(), CommonError> {
+        let source = &self.chain[0];
+        let done = HashSet::from([0]);
+
+        loop {
+            let page_item = paginator.create_page_item();
+            let mut data_map = HashMap::<String, PairValueEnum>::new();
+
+            let ret = self.run_step(&page_item, &data_map, &mut |c, query, rows| {
+                run_many_query(c, self.plan_name, query, rows)
+            }).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("page run failed {}:{} page {}", self.plan_name, source.id, paginator.page()), e)
+            })?.unwrap_or(PairValueEnum::Null);
+
+            let page = paginator.page();
+            let has_next = paginator.advance(&ret)?;
+            if page > 0 && get_ret_bind_param_cnt(&ret) == 0 {
+                break;
+            }
+
+            log_debug!(self.plan_name, "page running, name={} page={} rows={}", source.id.as_str(), page, get_ret_bind_param_cnt(&ret));
+            self.cache.lock().map_err(|e| {
+                CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+            })?.page = Some(page);
+            self.put_result(source, ret, &mut data_map)?;
+            let page_map = self.run_dag(dag, &done, data_map).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("page chain failed {} page {}", self.plan_name, page), e)
+            })?;
+            if let Some(wm) = watermark.as_mut() {
+                wm.push(&page_map)?;
+            }
+
+            if !has_next {
+                break;
+            }
+        }
+
         Ok(())
     }
-    
-    pub fn new(plan_name : &'a str, chain : &'a [PlanChain]) -> Self {
-        Self { plan_name, chain, cache : RefCell::new(QueryEntryCache::default()) }
+
+    // returns the next watermark when the plan has one
+    pub fn run(&self) -> Result<Option<String>, CommonError> {
+        if self.chain.len() <= 0 {
+            return Ok(None);
+        }
+
+        if !chk_conflict_bind_param(self.chain) {
+            return CommonError::new(&CommonDefaultErrorKind::Etc, "conflict idx bind or args").to_result();
+        }
+
+        let dag = ChainDag::new(self.chain).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("chain dag create failed {}", self.plan_name), e)
+        })?;
+
+        let mut watermark = self.watermark.map(WatermarkMax::new);
+        match Paginator::new(self.chain, self.transaction)? {
+            Some(paginator) => self.run_paginated(&dag, paginator, &mut watermark)?,
+            None => {
+                let data_map = self.run_dag(&dag, &HashSet::new(), HashMap::new())?;
+                if let Some(wm) = watermark.as_mut() {
+                    wm.push(&data_map)?;
+                }
+            }
+        }
+
+        watermark.map_or(Ok(None), |x| x.finish())
+    }
+
+    pub fn new(ctx : &'a QueryRunContext, chain : &'a [PlanChain], transaction : bool, watermark : Option<&'a PlanWatermark>) -> Self {
+        Self { plan_name : ctx.plan_name.as_str(), chain, ctx, transaction, watermark, cache : Mutex::new(QueryEntryCache::default()) }
     }
 }
\ No newline at end of file
@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use common_rs::logger::*;
+use mypip_types::config::plan::{PlanAssert, PlanChain};
+use crate::types::QueryRunContext;
+
+struct AssertTarget<'d> {
+    step : &'d str,
+    cols : Option<&'d HashMap<String, PairValueEnum>>,
+    rows : usize,
+}
+
+impl<'d> AssertTarget<'d> {
+    fn get_col(&self, col : &'_ str) -> Result<&'d [PairValueEnum], String> {
+        match self.cols.and_then(|m| m.get(col)) {
+            Some(PairValueEnum::Array(arr)) => Ok(arr.as_slice()),
+            Some(other) => Ok(std::slice::from_ref(other)),
+            None if self.rows == 0 => Ok(&[]),
+            None => Err(format!("column {}.{} not exists", self.step, col))
+        }
+    }
+}
+
+fn get_number(v : &'_ PairValueEnum) -> Option<f64> {
+    match v {
+        PairValueEnum::Int(x) => Some(*x as f64),
+        PairValueEnum::BigInt(x) => Some(*x as f64),
+        PairValueEnum::Double(x) => Some(*x),
+        PairValueEnum::Float(x) => Some(*x as f64),
+        _ => None
+    }
+}
+
+fn get_timestamp(v : &'_ PairValueEnum) -> Option<i64> {
+    match v {
+        PairValueEnum::Int(x) => Some(*x as i64),
+        PairValueEnum::BigInt(x) => Some(*x),
+        PairValueEnum::String(x) => super::value::parse_timestamp_millis(x.as_str()).ok(),
+        _ => None
+    }
+}
+
+fn check_row_count(rule : &'_ PlanAssert, t : &'_ AssertTarget<'_>) -> Result<(), String> {
+    if let Some(min) = rule.min_rows.filter(|x| t.rows < *x) {
+        return Err(format!("min_rows {} but got {}", min, t.rows));
+    }
+    if let Some(max) = rule.max_rows.filter(|x| t.rows > *x) {
+        return Err(format!("max_rows {} but got {}", max, t.rows));
+    }
+    Ok(())
+}
+
+fn check_not_null(rule : &'_ PlanAssert, t : &'_ AssertTarget<'_>) -> Result<(), String> {
+    for col in rule.not_null.iter().flatten() {
+        let values = t.get_col(col)?;
+        let nulls = values.iter().filter(|x| matches!(x, PairValueEnum::Null)).count();
+        if nulls > 0 {
+            return Err(format!("not_null {} has {} null rows", col, nulls));
+        }
+    }
+    Ok(())
+}
+
+fn check_unique(rule : &'_ PlanAssert, t : &'_ AssertTarget<'_>) -> Result<(), String> {
+    let keys = match rule.unique.as_ref().filter(|x| !x.is_empty()) {
+        Some(keys) => keys,
+        None => return Ok(())
+    };
+
+    let mut cols = Vec::with_capacity(keys.len());
+    for key in keys {
+        cols.push(t.get_col(key)?);
+    }
+
+    let mut exists = HashSet::with_capacity(t.rows);
+    for row in 0..t.rows {
+        let key : Vec<&PairValueEnum> = cols.iter().map(|c| c.get(row).unwrap_or(&PairValueEnum::Null)).collect();
+        if !exists.insert(format!("{:?}", key)) {
+            return Err(format!("unique [{}] duplicate {:?} at row {}", keys.join(", "), key, row));
+        }
+    }
+    Ok(())
+}
+
+fn check_range(rule : &'_ PlanAssert, t : &'_ AssertTarget<'_>) -> Result<(), String> {
+    for range in rule.range.iter().flatten() {
+        for (row, v) in t.get_col(range.column.as_str())?.iter().enumerate() {
+            if matches!(v, PairValueEnum::Null) {
+                continue;
+            }
+
+            let n = get_number(v).ok_or_else(|| format!("range {} not number {:?} at row {}", range.column, v, row))?;
+            if range.min.map_or(false, |min| n < min) || range.max.map_or(false, |max| n > max) {
+                return Err(format!("range {} [{:?}, {:?}] out of bounds {} at row {}", range.column, range.min, range.max, n, row));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_freshness(rule : &'_ PlanAssert, t : &'_ AssertTarget<'_>, ctx : &'_ QueryRunContext) -> Result<(), String> {
+    let fresh = match rule.freshness.as_ref() {
+        Some(f) => f,
+        None => return Ok(())
+    };
+
+    let mut latest : Option<i64> = None;
+    for (row, v) in t.get_col(fresh.column.as_str())?.iter().enumerate() {
+        if matches!(v, PairValueEnum::Null) {
+            continue;
+        }
+        let ts = get_timestamp(v).ok_or_else(|| format!("freshness {} not timestamp {:?} at row {}", fresh.column, v, row))?;
+        latest = Some(latest.map_or(ts, |x| x.max(ts)));
+    }
+
+    let latest = latest.ok_or_else(|| format!("freshness {} has no timestamp", fresh.column))?;
+    let age_ms = ctx.scheduled_time as i64 - latest;
+    if age_ms > (fresh.max_age_sec * 1000) as i64 {
+        return Err(format!("freshness {} max_age_sec {} but latest is {}s old", fresh.column, fresh.max_age_sec, age_ms / 1000));
+    }
+    Ok(())
+}
+
+pub(super) fn run_assert_chain(item : &'_ PlanChain, ctx : &'_ QueryRunContext, data_map : &HashMap<String, PairValueEnum>) -> Result<PairValueEnum, CommonError> {
+    let rule = item.assertion.as_ref().ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} not exists assert", item.id))
+    })?;
+
+    let data = data_map.get(rule.step.as_str()).ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} assert step {} not exists data", item.id, rule.step))
+    })?;
+
+    let target = AssertTarget {
+        step : rule.step.as_str(),
+        cols : if let PairValueEnum::Map(m) = data { Some(m) } else { None },
+        rows : super::get_ret_bind_param_cnt(data),
+    };
+
+    let checks : [(&str, Result<(), String>); 5] = [
+        ("row_count", check_row_count(rule, &target)),
+        ("not_null", check_not_null(rule, &target)),
+        ("unique", check_unique(rule, &target)),
+        ("range", check_range(rule, &target)),
+        ("freshness", check_freshness(rule, &target, ctx)),
+    ];
+
+    for (name, ret) in checks {
+        if let Err(msg) = ret {
+            return CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("assert {} failed on {}, check {} : {}", item.id, rule.step, name, msg)).to_result();
+        }
+    }
+
+    log_debug!(ctx.plan_name.as_str(), "assert passed, name={} step={} rows={}", item.id.as_str(), rule.step.as_str(), target.rows);
+    Ok(PairValueEnum::Null)
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use common_rs::logger::*;
+use mypip_global::constant::*;
+use mypip_global::GLOBAL;
+use mypip_types::config::plan::PlanChain;
+use mypip_types::interface::GlobalLayout;
+use super::merge::BindResultMerger;
+use super::placeholder::ChainQuery;
+use super::bind_mode::BindRow;
+use super::row_error::RowErrorHandler;
+
+pub(super) fn is_batch_mode(item : &'_ PlanChain) -> Result<bool, CommonError> {
+    match item.mode.as_ref().map(|x| x.as_str()) {
+        None | Some(crate::constant::CHAIN_MODE_ROW) => Ok(false),
+        Some(crate::constant::CHAIN_MODE_BATCH) => Ok(true),
+        Some(other) => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} not support mode {}", item.id, other)).to_result()
+    }
+}
+
+fn skip_quote(chars : &'_ [char], idx : usize) -> usize {
+    let quote = chars[idx];
+    let mut end = idx + 1;
+    while end < chars.len() && chars[end] != quote {
+        end += 1;
+    }
+    end + 1
+}
+
+fn find_values_tuple(chars : &'_ [char]) -> Option<(usize, usize)> {
+    let mut idx = 0;
+    let mut tuple_start = None;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        if c == '\'' || c == '"' {
+            idx = skip_quote(chars, idx);
+            continue;
+        }
+
+        let is_word_start = idx == 0 || !(chars[idx - 1].is_alphanumeric() || chars[idx - 1] == '_');
+        if is_word_start && idx + 6 <= chars.len() {
+            let word : String = chars[idx..idx + 6].iter().collect();
+            let is_word_end = chars.get(idx + 6).map_or(true, |x| !(x.is_alphanumeric() || *x == '_'));
+
+            if is_word_end && word.eq_ignore_ascii_case("values") {
+                let mut next = idx + 6;
+                while next < chars.len() && chars[next].is_whitespace() {
+                    next += 1;
+                }
+                if chars.get(next) == Some(&'(') {
+                    tuple_start = Some(next);
+                }
+                break;
+            }
+        }
+        idx += 1;
+    }
+
+    let start = tuple_start?;
+    let mut depth = 0;
+    let mut idx = start;
+
+    while idx < chars.len() {
+        match chars[idx] {
+            '\'' | '"' => {
+                idx = skip_quote(chars, idx);
+                continue;
+            },
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start, idx + 1));
+                }
+            },
+            _ => {}
+        }
+        idx += 1;
+    }
+
+    None
+}
+
+fn has_placeholder(chars : &'_ [char], is_numbered : bool) -> bool {
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        if c == '\'' || c == '"' {
+            idx = skip_quote(chars, idx);
+            continue;
+        }
+
+        if is_numbered && c == '$' && chars.get(idx + 1).map_or(false, |x| x.is_ascii_digit()) {
+            return true;
+        }
+        if !is_numbered && c == '?' {
+            return true;
+        }
+        idx += 1;
+    }
+    false
+}
+
+fn shift_pg_placeholder(tuple : &'_ [char], offset : usize) -> String {
+    let mut ret = String::with_capacity(tuple.len() + 8);
+    let mut idx = 0;
+
+    while idx < tuple.len() {
+        let c = tuple[idx];
+        if c == '\'' {
+            let end = skip_quote(tuple, idx).min(tuple.len());
+            ret.extend(tuple[idx..end].iter());
+            idx = end;
+            continue;
+        }
+
+        if c == '$' && tuple.get(idx + 1).map_or(false, |x| x.is_ascii_digit()) {
+            let mut end = idx + 1;
+            while end < tuple.len() && tuple[end].is_ascii_digit() {
+                end += 1;
+            }
+            let num : usize = tuple[idx + 1..end].iter().collect::<String>().parse().unwrap_or(0);
+            ret.push_str(format!("${}", num + offset).as_str());
+            idx = end;
+            continue;
+        }
+
+        ret.push(c);
+        idx += 1;
+    }
+
+    ret
+}
+
+fn create_multi_values_query(query : &'_ str, row_cnt : usize, param_cnt : usize, is_numbered : bool) -> Result<Option<String>, CommonError> {
+    let chars : Vec<char> = query.chars().collect();
+    let (start, end) = match find_values_tuple(chars.as_slice()) {
+        Some(range) => range,
+        None => return Ok(None)
+    };
+
+    if has_placeholder(&chars[..start], is_numbered) || has_placeholder(&chars[end..], is_numbered) {
+        return CommonError::new(&CommonDefaultErrorKind::NoSupport, "batch mode not support placeholder outside VALUES tuple").to_result();
+    }
+
+    let mut tuples = Vec::with_capacity(row_cnt);
+    for row in 0..row_cnt {
+        if is_numbered {
+            tuples.push(shift_pg_placeholder(&chars[start..end], row * param_cnt));
+        } else {
+            tuples.push(chars[start..end].iter().collect::<String>());
+        }
+    }
+
+    let head : String = chars[..start].iter().collect();
+    let tail : String = chars[end..].iter().collect();
+    Ok(Some(format!("{}{}{}", head, tuples.join(", "), tail)))
+}
+
+fn create_batch_query(conn_type : &'_ str, query : &'_ str, rows : &'_ [Vec<PairValueEnum>]) -> Result<Option<(String, Vec<PairValueEnum>)>, CommonError> {
+    let param_cnt = rows.iter().map(|x| x.len()).max().unwrap_or(0);
+    let flat_param = || {
+        let mut params = Vec::with_capacity(param_cnt * rows.len());
+        for row in rows {
+            params.extend(row.iter().cloned());
+            params.resize(params.len() + (param_cnt - row.len()), PairValueEnum::Null);
+        }
+        params
+    };
+
+    let ret = match conn_type {
+        CONN_TYPE_PG => {
+            if rows.len() * param_cnt > crate::constant::PG_MAX_BIND_PARAM {
+                return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("postgres batch {} rows x {} params over bind limit {}", rows.len(), param_cnt, crate::constant::PG_MAX_BIND_PARAM)).to_result();
+            }
+            create_multi_values_query(query, rows.len(), param_cnt, true)?.map(|q| (q, flat_param()))
+        },
+        CONN_TYPE_DUCKDB | CONN_TYPE_ODBC | CONN_TYPE_SQLITE => {
+            create_multi_values_query(query, rows.len(), param_cnt, false)?.map(|q| (q, flat_param()))
+        },
+        CONN_TYPE_SCYLLA => {
+            let stmt = query.trim().trim_end_matches(';');
+            let mut batch = String::from("BEGIN BATCH\n");
+            for _ in rows {
+                batch.push_str(stmt);
+                batch.push_str(";\n");
+            }
+            batch.push_str("APPLY BATCH");
+            Some((batch, flat_param()))
+        },
+        _ => None
+    };
+    Ok(ret)
+}
+
+fn run_rows_one_by_one(item : &'_ PlanChain, query : &'_ str, rows : Vec<Vec<PairValueEnum>>, row_err : &mut RowErrorHandler<'_>, merger : &mut BindResultMerger, runner : &mut super::ChainRunner<'_>) -> Result<(), CommonError> {
+    for row in rows {
+        match runner(item, query, vec![row.clone()]) {
+            Ok(ret) => {
+                row_err.success(1);
+                for one in ret {
+                    merger.push(one, None)?;
+                }
+            },
+            Err(row_e) => row_err.handle(row.as_slice(), row_e)?
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn run_batch_chain(item : &'_ PlanChain, chain_query : &'_ ChainQuery<'_>, plan_name : &'_ str, data_map : &HashMap<String, PairValueEnum>, bind_rows : &'_ [BindRow], row_err : &mut RowErrorHandler<'_>, runner : &mut super::ChainRunner<'_>) -> Result<PairValueEnum, CommonError> {
+    let conn_info = GLOBAL.get_conn_info(item.connection.as_str().into()).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get conn info failed {}:{}", plan_name, item.id), e)
+    })?;
+
+    let batch_size = item.batch_size.unwrap_or(match conn_info.conn_type.as_str() {
+        CONN_TYPE_SCYLLA => crate::constant::CHAIN_SCYLLA_DEFAULT_BATCH_SIZE,
+        _ => crate::constant::CHAIN_DEFAULT_BATCH_SIZE
+    });
+    if batch_size == 0 {
+        return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} batch_size must be > 0", item.id)).to_result();
+    }
+
+    if item.source_row_col.is_some() {
+        return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} source_row_col not support batch mode", item.id)).to_result();
+    }
+
+    let mut redis_pipeline = super::redis_cmd::RedisPipeline::new(chain_query.query.as_str(), None);
+    let mut merger = BindResultMerger::default();
+    let mut start = 0;
+
+    while start < bind_rows.len() {
+        let end = (start + batch_size).min(bind_rows.len());
+        let mut rows = Vec::with_capacity(end - start);
+
+        for bind_row in bind_rows[start..end].iter() {
+            match chain_query.create_bind(item, data_map, bind_row) {
+                Ok(d) => rows.push(d),
+                Err(e) => {
+                    row_err.handle(&[], CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("query bind param set failed {} row {}", plan_name, bind_row.idx), e))?;
+                }
+            }
+        }
+        if rows.is_empty() {
+            start = end;
+            continue;
+        }
+        if conn_info.conn_type == CONN_TYPE_REDIS {
+            redis_pipeline.run_rows(item, plan_name, rows.as_slice(), row_err, &mut merger, runner).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("batch run failed {}:{} rows {}..{}", plan_name, item.id, start, end), e)
+            })?;
+            start = end;
+            continue;
+        }
+
+        // postgres binds at most 65535 params per statement, a wide row splits the chunk further
+        let max_rows = match conn_info.conn_type.as_str() {
+            CONN_TYPE_PG => {
+                let param_cnt = rows.iter().map(|x| x.len()).max().unwrap_or(0);
+                if param_cnt > crate::constant::PG_MAX_BIND_PARAM {
+                    return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} row has {} params, over postgres bind limit {}", item.id, param_cnt, crate::constant::PG_MAX_BIND_PARAM)).to_result();
+                }
+                crate::constant::PG_MAX_BIND_PARAM / param_cnt.max(1)
+            },
+            _ => rows.len()
+        };
+
+        while !rows.is_empty() {
+            let rest = rows.split_off(max_rows.min(rows.len()));
+            let mut part = std::mem::replace(&mut rows, rest);
+            let row_cnt = part.len();
+
+            let batch_query = create_batch_query(conn_info.conn_type.as_str(), chain_query.query.as_str(), part.as_slice()).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("batch query create failed {}:{}", plan_name, item.id), e)
+            })?;
+            let bind_ret = match batch_query {
+                Some((query, params)) => {
+                    log_debug!(plan_name, "batch running, name={} rows={}", item.id.as_str(), row_cnt);
+                    runner(item, query.as_str(), vec![params])
+                },
+                None if row_err.is_abort() => {
+                    log_debug!(plan_name, "batch fallback to row, name={} rows={}", item.id.as_str(), row_cnt);
+                    runner(item, chain_query.query.as_str(), std::mem::take(&mut part))
+                },
+                None => {
+                    log_debug!(plan_name, "batch fallback to row, name={} rows={}", item.id.as_str(), row_cnt);
+                    run_rows_one_by_one(item, chain_query.query.as_str(), part, row_err, &mut merger, runner)?;
+                    continue;
+                }
+            }.map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("batch run failed {}:{} rows {}..{}", plan_name, item.id, start, end), e)
+            });
+
+            match bind_ret {
+                Ok(bind_ret) => {
+                    row_err.success(row_cnt);
+                    for ret in bind_ret {
+                        merger.push(ret, None)?;
+                    }
+                },
+                Err(e) if row_err.is_abort() => return Err(e),
+                Err(e) => {
+                    log_error!(plan_name, "{}, retry rows one by one", e);
+                    run_rows_one_by_one(item, chain_query.query.as_str(), part, row_err, &mut merger, runner)?;
+                }
+            }
+        }
+        start = end;
+    }
+
+    Ok(merger.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_rows(rows : usize, params : usize) -> Vec<Vec<PairValueEnum>> {
+        (0..rows).map(|r| (0..params).map(|p| PairValueEnum::BigInt((r * params + p) as i64)).collect()).collect()
+    }
+
+    #[test]
+    fn pg_batch_shifts_placeholder() {
+        let (query, params) = create_batch_query(CONN_TYPE_PG, "INSERT INTO t (a, b) VALUES ($1, $2)", create_rows(2, 2).as_slice()).unwrap().unwrap();
+        assert_eq!(query, "INSERT INTO t (a, b) VALUES ($1, $2), ($3, $4)");
+        assert_eq!(params.len(), 4);
+    }
+
+    #[test]
+    fn pg_batch_over_bind_limit_fails() {
+        let rows = create_rows(crate::constant::PG_MAX_BIND_PARAM / 2 + 1, 2);
+        assert!(create_batch_query(CONN_TYPE_PG, "INSERT INTO t (a, b) VALUES ($1, $2)", rows.as_slice()).is_err());
+
+        let rows = create_rows(crate::constant::PG_MAX_BIND_PARAM / 2, 2);
+        assert!(create_batch_query(CONN_TYPE_PG, "INSERT INTO t (a, b) VALUES ($1, $2)", rows.as_slice()).is_ok());
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use mypip_types::config::plan::PlanChain;
+
+#[derive(Debug, Default, Clone)]
+pub(super) struct BindRow {
+    pub idx : usize,
+    rows : HashMap<String, usize>,
+}
+
+impl BindRow {
+    fn new(idx : usize) -> Self {
+        BindRow { idx, rows : HashMap::new() }
+    }
+
+    pub fn get(&self, id : &'_ str) -> usize {
+        self.rows.get(id).copied().unwrap_or(self.idx)
+    }
+}
+
+fn fmt_counts(counts : &'_ [(&'_ str, usize)]) -> String {
+    counts.iter().map(|(id, cnt)| format!("{}={}", id, cnt)).collect::<Vec<String>>().join(", ")
+}
+
+fn is_count_mismatch(counts : &'_ [(&'_ str, usize)]) -> bool {
+    counts.windows(2).any(|x| x[0].1 != x[1].1)
+}
+
+fn create_zip_rows(item : &'_ PlanChain, counts : &'_ [(&'_ str, usize)]) -> Result<Vec<BindRow>, CommonError> {
+    let n = counts.first().map_or(0, |x| x.1);
+    if is_count_mismatch(counts) {
+        return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} zip row count mismatch [{}]", item.id, fmt_counts(counts))).to_result();
+    }
+
+    Ok((0..n).map(BindRow::new).collect())
+}
+
+fn create_broadcast_rows(item : &'_ PlanChain, counts : &'_ [(&'_ str, usize)]) -> Result<Vec<BindRow>, CommonError> {
+    let mut n = None;
+    for (_, cnt) in counts.iter().filter(|x| x.1 != 1) {
+        match n {
+            None => n = Some(*cnt),
+            Some(v) if v != *cnt => {
+                return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} broadcast row count mismatch [{}]", item.id, fmt_counts(counts))).to_result();
+            },
+            _ => {}
+        }
+    }
+
+    let n = n.unwrap_or(if counts.is_empty() { 0 } else { 1 });
+    Ok((0..n).map(|idx| {
+        let mut row = BindRow::new(idx);
+        for (id, _) in counts.iter().filter(|x| x.1 == 1) {
+            row.rows.insert(id.to_string(), 0);
+        }
+        row
+    }).collect())
+}
+
+fn create_cross_rows(counts : &'_ [(&'_ str, usize)]) -> Vec<BindRow> {
+    let total = if counts.is_empty() { 0 } else { counts.iter().map(|x| x.1).product() };
+
+    (0..total).map(|idx| {
+        let mut row = BindRow::new(idx);
+        let mut rest = idx;
+        for (id, cnt) in counts.iter().rev() {
+            row.rows.insert(id.to_string(), rest % cnt);
+            rest /= cnt;
+        }
+        row
+    }).collect()
+}
+
+fn get_lookup_key(v : &'_ PairValueEnum) -> Option<String> {
+    match v {
+        PairValueEnum::Int(x) => Some(format!("i:{}", x)),
+        PairValueEnum::BigInt(x) => Some(format!("i:{}", x)),
+        PairValueEnum::Float(x) => Some(format!("d:{}", x)),
+        PairValueEnum::Double(x) => Some(format!("d:{}", x)),
+        PairValueEnum::String(x) => Some(format!("s:{}", x)),
+        PairValueEnum::Bool(x) => Some(format!("b:{}", x)),
+        _ => None
+    }
+}
+
+fn get_lookup_col<'m>(m : &'m HashMap<String, PairValueEnum>, id : &'_ str, col : &'_ str) -> Result<&'m [PairValueEnum], CommonError> {
+    match m.get(id) {
+        Some(PairValueEnum::Map(cols)) => match cols.get(col) {
+            Some(PairValueEnum::Array(arr)) => Ok(arr.as_slice()),
+            _ => CommonError::new(&CommonDefaultErrorKind::NoData, format!("lookup column {}.{} not exists", id, col)).to_result()
+        },
+        _ => CommonError::new(&CommonDefaultErrorKind::NoData, format!("lookup step {} not exists", id)).to_result()
+    }
+}
+
+fn create_lookup_rows(item : &'_ PlanChain, counts : &'_ [(&'_ str, usize)], m : &HashMap<String, PairValueEnum>) -> Result<Vec<BindRow>, CommonError> {
+    let keys = item.lookup_key.as_ref().filter(|x| x.len() >= 2).ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} lookup need lookup_key [driver.col, other.col, ...]", item.id))
+    })?;
+
+    let mut refs = Vec::with_capacity(keys.len());
+    for k in keys {
+        let (id, col) = k.split_once('.').ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("{} lookup_key {} not id.col", item.id, k))
+        })?;
+        refs.push((id.trim(), col.trim()));
+    }
+
+    for (id, cnt) in counts {
+        if *cnt != 1 && !refs.iter().any(|x| x.0 == *id) {
+            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} lookup step {} not in lookup_key and has {} rows", item.id, id, cnt)).to_result();
+        }
+    }
+
+    let (driver_id, driver_col) = refs[0];
+    let driver = get_lookup_col(m, driver_id, driver_col)?;
+
+    let mut indexes = Vec::with_capacity(refs.len() - 1);
+    for (id, col) in refs.iter().skip(1) {
+        let mut index = HashMap::new();
+        for (row, v) in get_lookup_col(m, id, col)?.iter().enumerate() {
+            if let Some(key) = get_lookup_key(v) {
+                if index.insert(key, row).is_some() {
+                    return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} lookup {}.{} duplicate key {:?}", item.id, id, col, v)).to_result();
+                }
+            }
+        }
+        indexes.push((*id, *col, index));
+    }
+
+    let mut rows = Vec::with_capacity(driver.len());
+    for (idx, v) in driver.iter().enumerate() {
+        let key = get_lookup_key(v).ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} lookup {}.{} row {} key not support {:?}", item.id, driver_id, driver_col, idx, v))
+        })?;
+
+        let mut row = BindRow::new(idx);
+        for (id, col, index) in indexes.iter() {
+            let found = index.get(&key).ok_or_else(|| {
+                CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} lookup {}.{} not found {:?} (row {})", item.id, id, col, v, idx))
+            })?;
+            row.rows.insert(id.to_string(), *found);
+        }
+        for (id, cnt) in counts.iter().filter(|x| x.1 == 1) {
+            row.rows.entry(id.to_string()).or_insert(0);
+        }
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+// without bind_mode every bind step must have the same row count, unequal counts used to bind NULL silently
+pub(super) fn create_bind_rows(item : &'_ PlanChain, counts : &'_ [(&'_ str, usize)], m : &HashMap<String, PairValueEnum>) -> Result<Vec<BindRow>, CommonError> {
+    match item.bind_mode.as_ref().map(|x| x.as_str()) {
+        None if is_count_mismatch(counts) => {
+            CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} bind row count mismatch [{}], set bind_mode to broadcast, cross or lookup", item.id, fmt_counts(counts))).to_result()
+        },
+        None => create_zip_rows(item, counts),
+        Some(crate::constant::BIND_MODE_ZIP) => create_zip_rows(item, counts),
+        Some(crate::constant::BIND_MODE_BROADCAST) => create_broadcast_rows(item, counts),
+        Some(crate::constant::BIND_MODE_CROSS) => Ok(create_cross_rows(counts)),
+        Some(crate::constant::BIND_MODE_LOOKUP) => create_lookup_rows(item, counts, m),
+        Some(other) => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} not support bind_mode {}", item.id, other)).to_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constant::*;
+
+    fn create_item(bind_mode : Option<&'_ str>, lookup_key : Option<&'_ str>) -> PlanChain {
+        let mut item = PlanChain::default();
+        item.id = String::from("dst");
+        item.bind_mode = bind_mode.map(|x| x.to_string());
+        item.lookup_key = lookup_key.map(|x| x.split(',').map(|k| k.to_string()).collect());
+        item
+    }
+
+    fn get_idx(rows : &'_ [BindRow], id : &'_ str) -> Vec<usize> {
+        rows.iter().map(|x| x.get(id)).collect()
+    }
+
+    fn create_step(col : &'_ str, values : Vec<PairValueEnum>) -> PairValueEnum {
+        let mut m = HashMap::new();
+        m.insert(col.to_string(), PairValueEnum::Array(values));
+        PairValueEnum::Map(m)
+    }
+
+    #[test]
+    fn default_mode_needs_same_count() {
+        let item = create_item(None, None);
+        let rows = create_bind_rows(&item, &[("a", 2), ("b", 2)], &HashMap::new()).unwrap();
+        assert_eq!(get_idx(&rows, "a"), vec![0, 1]);
+        assert!(create_bind_rows(&item, &[("a", 2), ("b", 3)], &HashMap::new()).is_err());
+        assert!(create_bind_rows(&item, &[("a", 1), ("b", 3)], &HashMap::new()).is_err());
+        assert!(create_bind_rows(&item, &[("a", 0), ("b", 0)], &HashMap::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn zip_mode() {
+        let item = create_item(Some(BIND_MODE_ZIP), None);
+        let rows = create_bind_rows(&item, &[("a", 3), ("b", 3)], &HashMap::new()).unwrap();
+        assert_eq!(get_idx(&rows, "a"), vec![0, 1, 2]);
+        assert_eq!(get_idx(&rows, "b"), vec![0, 1, 2]);
+        assert!(create_bind_rows(&item, &[("a", 3), ("b", 2)], &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn broadcast_mode() {
+        let item = create_item(Some(BIND_MODE_BROADCAST), None);
+        let rows = create_bind_rows(&item, &[("one", 1), ("many", 3)], &HashMap::new()).unwrap();
+        assert_eq!(get_idx(&rows, "one"), vec![0, 0, 0]);
+        assert_eq!(get_idx(&rows, "many"), vec![0, 1, 2]);
+        assert_eq!(create_bind_rows(&item, &[("a", 1), ("b", 1)], &HashMap::new()).unwrap().len(), 1);
+        assert!(create_bind_rows(&item, &[("a", 2), ("b", 3)], &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn cross_mode() {
+        let item = create_item(Some(BIND_MODE_CROSS), None);
+        let rows = create_bind_rows(&item, &[("a", 2), ("b", 3)], &HashMap::new()).unwrap();
+        assert_eq!(get_idx(&rows, "a"), vec![0, 0, 0, 1, 1, 1]);
+        assert_eq!(get_idx(&rows, "b"), vec![0, 1, 2, 0, 1, 2]);
+        assert!(create_bind_rows(&item, &[("a", 2), ("b", 0)], &HashMap::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn lookup_mode() {
+        let mut m = HashMap::new();
+        m.insert(String::from("orders"), create_step("user_id", vec![PairValueEnum::Int(2), PairValueEnum::BigInt(1), PairValueEnum::Float(1.5)]));
+        m.insert(String::from("users"), create_step("id", vec![PairValueEnum::BigInt(1), PairValueEnum::Double(1.5), PairValueEnum::Int(2)]));
+        m.insert(String::from("conf"), create_step("v", vec![PairValueEnum::Int(9)]));
+
+        let item = create_item(Some(BIND_MODE_LOOKUP), Some("orders.user_id,users.id"));
+        let counts = [("orders", 3), ("users", 3), ("conf", 1)];
+        let rows = create_bind_rows(&item, &counts, &m).unwrap();
+        assert_eq!(get_idx(&rows, "orders"), vec![0, 1, 2]);
+        assert_eq!(get_idx(&rows, "users"), vec![2, 0, 1]);
+        assert_eq!(get_idx(&rows, "conf"), vec![0, 0, 0]);
+
+        m.insert(String::from("orders"), create_step("user_id", vec![PairValueEnum::Int(3)]));
+        assert!(create_bind_rows(&item, &[("orders", 1), ("users", 3)], &m).is_err());
+
+        let short = create_item(Some(BIND_MODE_LOOKUP), Some("orders.user_id"));
+        assert!(create_bind_rows(&short, &[("orders", 1)], &m).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+
+const F64_SAFE_INT : i64 = 1 << 53;
+
+fn cast_err<T>(v : &'_ PairValueEnum, cast : &'_ str, reason : &'_ str) -> Result<T, CommonError> {
+    CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("cast {:?} to {} failed : {}", v, cast, reason)).to_result()
+}
+
+fn cast_integral(v : &'_ PairValueEnum, cast : &'_ str, min : i64, max : i64) -> Result<i64, CommonError> {
+    let n = match v {
+        PairValueEnum::Int(x) => *x as i64,
+        PairValueEnum::BigInt(x) => *x,
+        PairValueEnum::Bool(x) => *x as i64,
+        PairValueEnum::Double(x) if x.fract() == 0.0 && x.abs() < F64_SAFE_INT as f64 => *x as i64,
+        PairValueEnum::Float(x) if x.fract() == 0.0 && x.abs() < F64_SAFE_INT as f32 => *x as i64,
+        PairValueEnum::Double(_) | PairValueEnum::Float(_) => return cast_err(v, cast, "fraction or out of range would be lost"),
+        PairValueEnum::String(x) => x.trim().parse::<i64>().or_else(|e| cast_err(v, cast, e.to_string().as_str()))?,
+        _ => return cast_err(v, cast, "impossible conversion")
+    };
+
+    if n < min || n > max {
+        return cast_err(v, cast, "out of range");
+    }
+    Ok(n)
+}
+
+fn cast_double(v : &'_ PairValueEnum, cast : &'_ str) -> Result<f64, CommonError> {
+    match v {
+        PairValueEnum::Int(x) => Ok(*x as f64),
+        PairValueEnum::BigInt(x) if x.abs() <= F64_SAFE_INT => Ok(*x as f64),
+        PairValueEnum::BigInt(_) => cast_err(v, cast, "precision would be lost"),
+        PairValueEnum::Double(x) => Ok(*x),
+        PairValueEnum::Float(x) => Ok(*x as f64),
+        PairValueEnum::String(x) => x.trim().parse::<f64>().or_else(|e| cast_err(v, cast, e.to_string().as_str())),
+        _ => cast_err(v, cast, "impossible conversion")
+    }
+}
+
+fn cast_text(v : &'_ PairValueEnum, cast : &'_ str) -> Result<String, CommonError> {
+    match v {
+        PairValueEnum::Int(x) => Ok(x.to_string()),
+        PairValueEnum::BigInt(x) => Ok(x.to_string()),
+        PairValueEnum::Double(x) => Ok(x.to_string()),
+        PairValueEnum::Float(x) => Ok(x.to_string()),
+        PairValueEnum::Bool(x) => Ok(x.to_string()),
+        PairValueEnum::String(x) => Ok(x.clone()),
+        PairValueEnum::Bin(x) => String::from_utf8(x.clone()).or_else(|_| cast_err(v, cast, "bytes not utf-8")),
+        _ => cast_err(v, cast, "impossible conversion")
+    }
+}
+
+fn cast_bool(v : &'_ PairValueEnum, cast : &'_ str) -> Result<bool, CommonError> {
+    match v {
+        PairValueEnum::Bool(x) => Ok(*x),
+        PairValueEnum::Int(0) | PairValueEnum::BigInt(0) => Ok(false),
+        PairValueEnum::Int(1) | PairValueEnum::BigInt(1) => Ok(true),
+        PairValueEnum::String(x) => match super::value::convert_str_value(Some(x.clone()), Some("bool")) {
+            Ok(PairValueEnum::Bool(b)) => Ok(b),
+            _ => cast_err(v, cast, "not bool literal")
+        },
+        _ => cast_err(v, cast, "only 0, 1 or bool literal")
+    }
+}
+
+fn cast_bytes(v : &'_ PairValueEnum, cast : &'_ str) -> Result<Vec<u8>, CommonError> {
+    match v {
+        PairValueEnum::Bin(x) => Ok(x.clone()),
+        PairValueEnum::String(x) => Ok(x.as_bytes().to_vec()),
+        _ => cast_err(v, cast, "impossible conversion")
+    }
+}
+
+fn cast_timestamp_ms(v : &'_ PairValueEnum, cast : &'_ str) -> Result<i64, CommonError> {
+    match v {
+        PairValueEnum::String(x) => super::value::parse_timestamp_millis(x.as_str()).or_else(|_| cast_err(v, cast, "not timestamp")),
+        PairValueEnum::Bool(_) => cast_err(v, cast, "impossible conversion"),
+        _ => cast_integral(v, cast, i64::MIN, i64::MAX)
+    }
+}
+
+pub(super) fn cast_value(v : PairValueEnum, cast : &'_ str) -> Result<PairValueEnum, CommonError> {
+    if let PairValueEnum::Null = v {
+        return Ok(v);
+    }
+
+    let cast = cast.to_lowercase();
+    let ret = match cast.as_str() {
+        "int" => PairValueEnum::Int(cast_integral(&v, cast.as_str(), i32::MIN as i64, i32::MAX as i64)? as i32),
+        "bigint" => PairValueEnum::BigInt(cast_integral(&v, cast.as_str(), i64::MIN, i64::MAX)?),
+        "double" => PairValueEnum::Double(cast_double(&v, cast.as_str())?),
+        "text" | "string" => PairValueEnum::String(cast_text(&v, cast.as_str())?),
+        "bool" => PairValueEnum::Bool(cast_bool(&v, cast.as_str())?),
+        "bytes" => PairValueEnum::Bin(cast_bytes(&v, cast.as_str())?),
+        "timestamp_ms" => PairValueEnum::BigInt(cast_timestamp_ms(&v, cast.as_str())?),
+        other => return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support cast {}", other)).to_result()
+    };
+
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn null_passes_through() {
+        for cast in ["int", "bigint", "double", "text", "bool", "bytes", "timestamp_ms", "unknown"] {
+            assert!(matches!(cast_value(PairValueEnum::Null, cast), Ok(PairValueEnum::Null)), "{}", cast);
+        }
+    }
+
+    #[test]
+    fn int_range() {
+        assert!(matches!(cast_value(PairValueEnum::BigInt(i32::MAX as i64), "int"), Ok(PairValueEnum::Int(i32::MAX))));
+        assert!(matches!(cast_value(PairValueEnum::BigInt(i32::MIN as i64), "INT"), Ok(PairValueEnum::Int(i32::MIN))));
+        assert!(cast_value(PairValueEnum::BigInt(i32::MAX as i64 + 1), "int").is_err());
+        assert!(cast_value(PairValueEnum::BigInt(i32::MIN as i64 - 1), "int").is_err());
+        assert!(cast_value(PairValueEnum::String(String::from("99999999999")), "int").is_err());
+        assert!(matches!(cast_value(PairValueEnum::String(String::from(" 42 ")), "int"), Ok(PairValueEnum::Int(42))));
+    }
+
+    #[test]
+    fn fraction_is_rejected() {
+        assert!(matches!(cast_value(PairValueEnum::Double(3.0), "bigint"), Ok(PairValueEnum::BigInt(3))));
+        assert!(matches!(cast_value(PairValueEnum::Float(-2.0), "int"), Ok(PairValueEnum::Int(-2))));
+        assert!(cast_value(PairValueEnum::Double(3.5), "bigint").is_err());
+        assert!(cast_value(PairValueEnum::Float(0.25), "int").is_err());
+        assert!(cast_value(PairValueEnum::String(String::from("1.5")), "int").is_err());
+        assert!(cast_value(PairValueEnum::Double(1e20), "bigint").is_err());
+        assert!(cast_value(PairValueEnum::Double(f64::NAN), "bigint").is_err());
+    }
+
+    #[test]
+    fn double_precision() {
+        let safe = 1i64 << 53;
+        assert!(matches!(cast_value(PairValueEnum::BigInt(safe), "double"), Ok(PairValueEnum::Double(x)) if x == safe as f64));
+        assert!(matches!(cast_value(PairValueEnum::BigInt(-safe), "double"), Ok(PairValueEnum::Double(x)) if x == -safe as f64));
+        assert!(cast_value(PairValueEnum::BigInt(safe + 1), "double").is_err());
+        assert!(cast_value(PairValueEnum::BigInt(i64::MAX), "double").is_err());
+        assert!(cast_value(PairValueEnum::Bool(true), "double").is_err());
+    }
+
+    #[test]
+    fn bool_and_text() {
+        assert!(matches!(cast_value(PairValueEnum::Int(1), "bool"), Ok(PairValueEnum::Bool(true))));
+        assert!(matches!(cast_value(PairValueEnum::String(String::from("no")), "bool"), Ok(PairValueEnum::Bool(false))));
+        assert!(cast_value(PairValueEnum::Int(2), "bool").is_err());
+        assert!(cast_value(PairValueEnum::String(String::from("maybe")), "bool").is_err());
+
+        assert!(matches!(cast_value(PairValueEnum::BigInt(-7), "text"), Ok(PairValueEnum::String(x)) if x == "-7"));
+        assert!(cast_value(PairValueEnum::Bin(vec![0xff, 0xfe]), "text").is_err());
+        assert!(cast_value(PairValueEnum::Int(1), "bytes").is_err());
+    }
+
+    #[test]
+    fn timestamp_and_unknown_cast() {
+        assert!(matches!(cast_value(PairValueEnum::String(String::from("1970-01-02")), "timestamp_ms"), Ok(PairValueEnum::BigInt(86_400_000))));
+        assert!(cast_value(PairValueEnum::String(String::from("yesterday")), "timestamp_ms").is_err());
+        assert!(cast_value(PairValueEnum::Bool(true), "timestamp_ms").is_err());
+        assert!(cast_value(PairValueEnum::Int(1), "decimal").is_err());
+    }
+}
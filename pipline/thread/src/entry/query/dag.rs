@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use mypip_types::config::plan::PlanChain;
+
+pub(super) struct ChainDag {
+    pub waves : Vec<Vec<usize>>,
+    deps : Vec<Vec<usize>>,
+}
+
+impl ChainDag {
+    pub fn new(chain : &'_ [PlanChain]) -> Result<Self, CommonError> {
+        let mut id_map = HashMap::with_capacity(chain.len());
+        for (idx, item) in chain.iter().enumerate() {
+            if id_map.insert(item.id.as_str(), idx).is_some() {
+                return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("duplicate chain id {}", item.id)).to_result();
+            }
+        }
+
+        let mut deps = Vec::with_capacity(chain.len());
+        for (idx, item) in chain.iter().enumerate() {
+            let dep = match item.after.as_ref() {
+                Some(after) => {
+                    let mut v = Vec::with_capacity(after.len());
+                    for id in after {
+                        let dep_idx = id_map.get(id.as_str()).ok_or_else(|| {
+                            CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} after not exists chain {}", item.id, id))
+                        })?;
+                        v.push(*dep_idx);
+                    }
+                    v
+                },
+                None if idx > 0 => vec![idx - 1],
+                None => Vec::new()
+            };
+            deps.push(dep);
+        }
+
+        let mut level : Vec<Option<usize>> = vec![None; chain.len()];
+        let mut waves : Vec<Vec<usize>> = Vec::new();
+        let mut done = 0;
+
+        while done < chain.len() {
+            let mut wave = Vec::new();
+            for idx in 0..chain.len() {
+                if level[idx].is_some() {
+                    continue;
+                }
+                if deps[idx].iter().all(|d| level[*d].map_or(false, |l| l < waves.len())) {
+                    wave.push(idx);
+                }
+            }
+
+            if wave.is_empty() {
+                let cycle : Vec<&str> = (0..chain.len()).filter(|x| level[*x].is_none()).map(|x| chain[x].id.as_str()).collect();
+                return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("chain after has cycle [{}]", cycle.join(", "))).to_result();
+            }
+
+            for idx in wave.iter() {
+                level[*idx] = Some(waves.len());
+            }
+            done += wave.len();
+            waves.push(wave);
+        }
+
+        Ok(ChainDag { waves, deps })
+    }
+
+    pub fn find_failed_dep(&self, idx : usize, failed : &HashSet<usize>) -> Option<usize> {
+        self.deps[idx].iter().find(|d| failed.contains(d)).copied()
+    }
+}
+
+// the last member of each tx_group in run order, a group commits only after it, even when other steps run in between
+pub(super) fn find_tx_group_ends<'a>(order : &'_ [usize], get_group : impl Fn(usize) -> Option<&'a str>) -> HashSet<usize> {
+    let mut ends = HashMap::<&'a str, usize>::new();
+    for idx in order.iter().copied() {
+        if let Some(group) = get_group(idx) {
+            ends.insert(group, idx);
+        }
+    }
+    ends.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_chain(steps : &'_ [(&'_ str, Option<&'_ str>)]) -> Vec<PlanChain> {
+        steps.iter().map(|(id, after)| {
+            let mut item = PlanChain::default();
+            item.id = id.to_string();
+            item.after = after.map(|x| x.split(',').filter(|a| !a.is_empty()).map(|a| a.to_string()).collect());
+            item
+        }).collect()
+    }
+
+    #[test]
+    fn default_is_sequential() {
+        let dag = ChainDag::new(&create_chain(&[("a", None), ("b", None), ("c", None)])).unwrap();
+        assert_eq!(dag.waves, vec![vec![0], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn fan_out_and_join() {
+        let chain = create_chain(&[
+            ("src", Some("")),
+            ("pg", Some("src")),
+            ("redis", Some("src")),
+            ("join", Some("redis,pg")),
+        ]);
+        let dag = ChainDag::new(&chain).unwrap();
+        assert_eq!(dag.waves, vec![vec![0], vec![1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn dependency_declared_later_runs_first() {
+        let dag = ChainDag::new(&create_chain(&[("a", Some("b")), ("b", Some(""))])).unwrap();
+        assert_eq!(dag.waves, vec![vec![1], vec![0]]);
+    }
+
+    #[test]
+    fn cycle_and_unknown_dependency_fail() {
+        assert!(ChainDag::new(&create_chain(&[("a", Some("b")), ("b", Some("a"))])).is_err());
+        assert!(ChainDag::new(&create_chain(&[("a", Some("a"))])).is_err());
+        assert!(ChainDag::new(&create_chain(&[("a", Some("x"))])).is_err());
+        assert!(ChainDag::new(&create_chain(&[("a", None), ("a", None)])).is_err());
+    }
+
+    #[test]
+    fn failed_dependency_propagates() {
+        let chain = create_chain(&[
+            ("src", Some("")),
+            ("pg", Some("src")),
+            ("redis", Some("src")),
+            ("join", Some("pg,redis")),
+            ("tail", Some("join")),
+        ]);
+        let dag = ChainDag::new(&chain).unwrap();
+
+        let mut failed = HashSet::new();
+        failed.insert(1);
+        assert_eq!(dag.find_failed_dep(2, &failed), None);
+        assert_eq!(dag.find_failed_dep(3, &failed), Some(1));
+
+        failed.insert(3);
+        assert_eq!(dag.find_failed_dep(4, &failed), Some(3));
+    }
+
+    #[test]
+    fn tx_group_ends_after_interleaved_step() {
+        let groups = [Some("g"), None, Some("g"), Some("h"), None];
+        let get_group = |x : usize| groups[x];
+
+        // group g is split by the non-tx step 1, it must stay open until step 2
+        let ends = find_tx_group_ends(&[0, 1, 2, 3, 4], get_group);
+        assert_eq!(ends, HashSet::from([2, 3]));
+
+        // wave order puts 3 before 2, g still ends at 2
+        let ends = find_tx_group_ends(&[0, 3, 1, 2, 4], get_group);
+        assert_eq!(ends, HashSet::from([2, 3]));
+
+        let ends = find_tx_group_ends(&[1, 4], get_group);
+        assert!(ends.is_empty());
+    }
+}
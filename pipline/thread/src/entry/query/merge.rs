@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+
+#[derive(Default)]
+pub(super) struct BindResultMerger {
+    cols : HashMap<String, Vec<PairValueEnum>>,
+    rows : usize,
+}
+
+impl BindResultMerger {
+    fn push_col(&mut self, key : &'_ str, values : Vec<PairValueEnum>) {
+        let rows = self.rows;
+        let col = self.cols.entry(key.to_string()).or_insert_with(|| {
+            vec![PairValueEnum::Null; rows]
+        });
+        col.extend(values);
+    }
+
+    fn fill_null(&mut self) {
+        for col in self.cols.values_mut() {
+            if col.len() < self.rows {
+                col.resize(self.rows, PairValueEnum::Null);
+            }
+        }
+    }
+
+    // every column of one result must have the same row count, a scalar counts as one row
+    fn get_map_rows(map : &'_ HashMap<String, PairValueEnum>) -> Result<usize, CommonError> {
+        let mut rows : Option<(&'_ str, usize)> = None;
+        for (k, v) in map.iter() {
+            let cnt = match v {
+                PairValueEnum::Array(arr) => arr.len(),
+                _ => 1
+            };
+            match rows {
+                None => rows = Some((k.as_str(), cnt)),
+                Some((first, first_cnt)) if first_cnt != cnt => {
+                    return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("result column {} has {} rows, {} has {}", k, cnt, first, first_cnt)).to_result();
+                },
+                Some(_) => {}
+            }
+        }
+        Ok(rows.map_or(0, |x| x.1))
+    }
+
+    pub fn push(&mut self, ret : PairValueEnum, source_row : Option<(&'_ str, usize)>) -> Result<(), CommonError> {
+        let add_rows = match ret {
+            PairValueEnum::Null => 0,
+            PairValueEnum::Map(map) => {
+                let add_rows = Self::get_map_rows(&map)?;
+                for (k, v) in map {
+                    match v {
+                        PairValueEnum::Array(arr) => self.push_col(k.as_str(), arr),
+                        other => self.push_col(k.as_str(), vec![other])
+                    }
+                }
+                add_rows
+            },
+            other => {
+                self.push_col(crate::constant::CHAIN_MERGE_VALUE_COL, vec![other]);
+                1
+            }
+        };
+
+        if let Some((col, idx)) = source_row {
+            self.push_col(col, vec![PairValueEnum::BigInt(idx as i64); add_rows]);
+        }
+
+        self.rows += add_rows;
+        self.fill_null();
+        Ok(())
+    }
+
+    pub fn finish(self) -> PairValueEnum {
+        PairValueEnum::Map(self.cols.into_iter().map(|(k, v)| {
+            (k, PairValueEnum::Array(v))
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_ret(cols : Vec<(&'_ str, PairValueEnum)>) -> PairValueEnum {
+        PairValueEnum::Map(cols.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+
+    fn get_col(ret : &'_ PairValueEnum, col : &'_ str) -> Vec<PairValueEnum> {
+        match ret {
+            PairValueEnum::Map(map) => match map.get(col) {
+                Some(PairValueEnum::Array(arr)) => arr.clone(),
+                other => panic!("column {} not array {:?}", col, other)
+            },
+            other => panic!("not map {:?}", other)
+        }
+    }
+
+    #[test]
+    fn scalar_column_is_one_row() {
+        let mut merger = BindResultMerger::default();
+        merger.push(create_ret(vec![("id", PairValueEnum::BigInt(1))]), Some(("src_row", 0))).unwrap();
+        merger.push(create_ret(vec![("id", PairValueEnum::Array(vec![PairValueEnum::BigInt(2), PairValueEnum::BigInt(3)]))]), Some(("src_row", 1))).unwrap();
+
+        let ret = merger.finish();
+        assert_eq!(get_col(&ret, "id").len(), 3);
+        let src_row = get_col(&ret, "src_row");
+        assert!(matches!(src_row.as_slice(), [PairValueEnum::BigInt(0), PairValueEnum::BigInt(1), PairValueEnum::BigInt(1)]));
+    }
+
+    #[test]
+    fn uneven_columns_fail() {
+        let mut merger = BindResultMerger::default();
+        let ret = create_ret(vec![
+            ("a", PairValueEnum::Array(vec![PairValueEnum::BigInt(1), PairValueEnum::BigInt(2)])),
+            ("b", PairValueEnum::Array(vec![PairValueEnum::BigInt(1)])),
+        ]);
+        assert!(merger.push(ret, None).is_err());
+
+        let ret = create_ret(vec![
+            ("a", PairValueEnum::Array(vec![PairValueEnum::BigInt(1), PairValueEnum::BigInt(2)])),
+            ("b", PairValueEnum::String(String::from("x"))),
+        ]);
+        assert!(merger.push(ret, None).is_err());
+    }
+
+    #[test]
+    fn missing_column_filled_with_null() {
+        let mut merger = BindResultMerger::default();
+        merger.push(create_ret(vec![("a", PairValueEnum::BigInt(1))]), None).unwrap();
+        merger.push(create_ret(vec![("b", PairValueEnum::BigInt(2))]), None).unwrap();
+
+        let ret = merger.finish();
+        assert!(matches!(get_col(&ret, "a").as_slice(), [PairValueEnum::BigInt(1), PairValueEnum::Null]));
+        assert!(matches!(get_col(&ret, "b").as_slice(), [PairValueEnum::Null, PairValueEnum::BigInt(2)]));
+    }
+}
@@ -0,0 +1,123 @@
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use mypip_types::config::plan::{PlanChain, PlanChainArgs, PlanPaginate};
+use crate::constant::*;
+
+pub(super) struct Paginator<'p> {
+    item : &'p PlanChain,
+    rule : &'p PlanPaginate,
+    page : usize,
+    offset : usize,
+    key : (Option<String>, Option<String>),
+}
+
+fn convert_key_arg(v : &'_ PairValueEnum) -> Result<(Option<String>, Option<String>), CommonError> {
+    match v {
+        PairValueEnum::Null => Ok((None, Some(String::from("null")))),
+        PairValueEnum::Int(x) => Ok((Some(x.to_string()), Some(String::from("int")))),
+        PairValueEnum::BigInt(x) => Ok((Some(x.to_string()), Some(String::from("bigint")))),
+        PairValueEnum::Double(x) => Ok((Some(x.to_string()), Some(String::from("double")))),
+        PairValueEnum::String(x) => Ok((Some(x.clone()), Some(String::from("text")))),
+        other => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support page key {:?}", other)).to_result()
+    }
+}
+
+fn create_page_arg(name : &'_ str, data : Option<String>, type_name : Option<String>) -> PlanChainArgs {
+    PlanChainArgs {
+        data : data.unwrap_or_default(),
+        idx : 0,
+        name : Some(name.to_string()),
+        type_name : type_name.or_else(|| Some(String::from("null")))
+    }
+}
+
+impl<'p> Paginator<'p> {
+    pub fn new(chain : &'p [PlanChain], transaction : bool) -> Result<Option<Self>, CommonError> {
+        if let Some(item) = chain.iter().skip(1).find(|x| x.paginate.is_some()) {
+            return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} paginate only support first chain", item.id)).to_result();
+        }
+
+        let item = match chain.first() {
+            Some(item) => item,
+            None => return Ok(None)
+        };
+        let rule = match item.paginate.as_ref() {
+            Some(rule) => rule,
+            None => return Ok(None)
+        };
+
+        if rule.page_size == 0 {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} page_size must be > 0", item.id)).to_result();
+        }
+        if transaction || item.tx_group.is_some() || item.kind.as_ref().map_or(false, |x| x != CHAIN_KIND_QUERY) {
+            return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} paginate only support non transaction query chain", item.id)).to_result();
+        }
+        if !item.query.contains(PAGINATE_ARG_LIMIT) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} paginate query need :{}", item.id, PAGINATE_ARG_LIMIT)).to_result();
+        }
+
+        match rule.mode.as_str() {
+            PAGINATE_MODE_OFFSET => {},
+            PAGINATE_MODE_KEYSET if rule.column.is_none() || !item.query.contains(PAGINATE_ARG_KEY) => {
+                return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} keyset paginate need column and :{}", item.id, PAGINATE_ARG_KEY)).to_result();
+            },
+            PAGINATE_MODE_KEYSET if rule.initial.is_none() => {
+                return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} keyset paginate need initial key", item.id)).to_result();
+            },
+            PAGINATE_MODE_KEYSET => {},
+            other => {
+                return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} not support paginate mode {}", item.id, other)).to_result();
+            }
+        }
+
+        Ok(Some(Paginator { item, rule, page : 0, offset : 0, key : (rule.initial.clone(), rule.type_name.clone()) }))
+    }
+
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    pub fn create_page_item(&self) -> PlanChain {
+        let mut page_item = self.item.clone();
+        let args = page_item.args.get_or_insert_with(Vec::new);
+
+        args.push(create_page_arg(PAGINATE_ARG_LIMIT, Some(self.rule.page_size.to_string()), Some(String::from("bigint"))));
+        if self.rule.mode == PAGINATE_MODE_OFFSET {
+            args.push(create_page_arg(PAGINATE_ARG_OFFSET, Some(self.offset.to_string()), Some(String::from("bigint"))));
+        } else {
+            args.push(create_page_arg(PAGINATE_ARG_KEY, self.key.0.clone(), self.key.1.clone()));
+        }
+        page_item
+    }
+
+    pub fn advance(&mut self, ret : &'_ PairValueEnum) -> Result<bool, CommonError> {
+        let rows = super::get_ret_bind_param_cnt(ret);
+        self.page += 1;
+        self.offset += rows;
+
+        if rows < self.rule.page_size {
+            return Ok(false);
+        }
+
+        if let Some(col) = self.rule.column.as_ref().filter(|_| self.rule.mode == PAGINATE_MODE_KEYSET) {
+            let last = match ret {
+                PairValueEnum::Map(m) => match m.get(col) {
+                    Some(PairValueEnum::Array(arr)) => arr.last(),
+                    _ => None
+                },
+                _ => None
+            }.ok_or_else(|| {
+                CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} paginate column {} not exists", self.item.id, col))
+            })?;
+
+            let next = convert_key_arg(last)?;
+            if next.0.is_none() || next == self.key {
+                return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} paginate key {} not advanced {:?}", self.item.id, col, last)).to_result();
+            }
+            self.key = next;
+        }
+
+        Ok(true)
+    }
+}
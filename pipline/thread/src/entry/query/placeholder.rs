@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use mypip_global::constant::*;
+use mypip_global::GLOBAL;
+use mypip_types::config::plan::PlanChain;
+use mypip_types::interface::GlobalLayout;
+use crate::types::QueryRunContext;
+use super::bind_mode::BindRow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Placeholder {
+    Name(String),
+    Ref(String, String),
+}
+
+pub(super) struct ChainQuery<'c> {
+    pub query : String,
+    params : Option<Vec<Placeholder>>,
+    ctx : &'c QueryRunContext,
+}
+
+fn is_name_start(c : char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_name_char(c : char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn find_seq(chars : &'_ [char], from : usize, seq : &'_ [char]) -> Option<usize> {
+    (from..chars.len()).find(|x| chars[*x..].starts_with(seq))
+}
+
+fn skip_verbatim(chars : &'_ [char], idx : usize) -> Option<usize> {
+    let c = chars[idx];
+    let next = chars.get(idx + 1).copied();
+
+    if c == '\'' || c == '"' {
+        let end = (idx + 1..chars.len()).find(|x| chars[*x] == c).map_or(chars.len(), |x| x + 1);
+        return Some(end);
+    }
+    if c == '-' && next == Some('-') {
+        return Some((idx..chars.len()).find(|x| chars[*x] == '\n').unwrap_or(chars.len()));
+    }
+    if c == '/' && next == Some('*') {
+        return Some(find_seq(chars, idx + 2, &['*', '/']).map_or(chars.len(), |x| x + 2));
+    }
+    if c == '$' && next.map_or(false, |x| x == '$' || is_name_start(x)) {
+        let tag_end = (idx + 1..chars.len()).find(|x| !is_name_char(chars[*x]))?;
+        if chars[tag_end] != '$' {
+            return None;
+        }
+        let tag = &chars[idx..tag_end + 1];
+        return Some(find_seq(chars, tag_end + 1, tag).map_or(chars.len(), |x| x + tag.len()));
+    }
+    None
+}
+
+fn parse_named_query(query : &'_ str, names : &'_ [&'_ str]) -> Result<Vec<(String, Option<Placeholder>)>, CommonError> {
+    let chars : Vec<char> = query.chars().collect();
+    let mut parts = Vec::new();
+    let mut text = String::with_capacity(query.len());
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+
+        if let Some(end) = skip_verbatim(chars.as_slice(), idx) {
+            text.extend(chars[idx..end].iter());
+            idx = end;
+            continue;
+        }
+
+        if c == ':' && chars.get(idx + 1) == Some(&':') {
+            text.push_str("::");
+            idx += 2;
+            continue;
+        }
+
+        if c == ':' && chars.get(idx + 1).map_or(false, |x| is_name_start(*x)) {
+            let mut end = idx + 1;
+            while end < chars.len() && is_name_char(chars[end]) {
+                end += 1;
+            }
+            let name : String = chars[idx + 1..end].iter().collect();
+            if !names.contains(&name.as_str()) {
+                text.extend(chars[idx..end].iter());
+                idx = end;
+                continue;
+            }
+            parts.push((std::mem::take(&mut text), Some(Placeholder::Name(name))));
+            idx = end;
+            continue;
+        }
+
+        if c == '{' && chars.get(idx + 1) == Some(&'{') {
+            let mut end = idx + 2;
+            while end + 1 < chars.len() && !(chars[end] == '}' && chars[end + 1] == '}') {
+                end += 1;
+            }
+            if end + 1 >= chars.len() {
+                return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("unterminated placeholder at {}", idx)).to_result();
+            }
+
+            let inner : String = chars[idx + 2..end].iter().collect();
+            let inner = inner.trim();
+            let placeholder = match inner.split_once('.') {
+                Some((id, key)) if !id.trim().is_empty() && !key.trim().is_empty() => {
+                    Placeholder::Ref(id.trim().to_string(), key.trim().to_string())
+                },
+                _ if !inner.is_empty() => Placeholder::Name(inner.to_string()),
+                _ => {
+                    return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("empty placeholder at {}", idx)).to_result();
+                }
+            };
+
+            parts.push((std::mem::take(&mut text), Some(placeholder)));
+            idx = end + 2;
+            continue;
+        }
+
+        text.push(c);
+        idx += 1;
+    }
+
+    parts.push((text, None));
+    Ok(parts)
+}
+
+fn rewrite_named_query(query : &'_ str, conn_type : &'_ str, names : &'_ [&'_ str]) -> Result<Option<(String, Vec<Placeholder>)>, CommonError> {
+    let parts = parse_named_query(query, names)?;
+    if parts.iter().all(|(_, p)| p.is_none()) {
+        return Ok(None);
+    }
+
+    let mut ret = String::with_capacity(query.len());
+    let mut params : Vec<Placeholder> = Vec::new();
+
+    for (text, placeholder) in parts {
+        ret.push_str(text.as_str());
+        let placeholder = match placeholder {
+            Some(p) => p,
+            None => continue
+        };
+
+        match conn_type {
+            CONN_TYPE_PG => {
+                let pos = match params.iter().position(|x| *x == placeholder) {
+                    Some(pos) => pos,
+                    None => {
+                        params.push(placeholder);
+                        params.len() - 1
+                    }
+                };
+                ret.push_str(format!("${}", pos + 1).as_str());
+            },
+            _ => {
+                ret.push('?');
+                params.push(placeholder);
+            }
+        }
+    }
+
+    Ok(Some((ret, params)))
+}
+
+fn get_declared_names(item : &'_ PlanChain) -> Vec<&'_ str> {
+    let args = item.args.iter().flatten().filter_map(|x| x.name.as_deref());
+    let binds = item.bind.iter().flatten().filter_map(|x| x.name.as_deref());
+    args.chain(binds).collect()
+}
+
+// every declared name must be used by the query and every {{name}} must be declared, otherwise values are silently dropped
+fn check_named_params(names : &'_ [&'_ str], params : Option<&'_ [Placeholder]>) -> Result<(), String> {
+    let used : Vec<&str> = params.iter().flat_map(|x| x.iter()).filter_map(|x| match x {
+        Placeholder::Name(n) => Some(n.as_str()),
+        Placeholder::Ref(_, _) => None
+    }).collect();
+
+    let mut unused : Vec<&str> = names.iter().copied().filter(|x| !used.contains(x)).collect();
+    let mut unknown : Vec<&str> = used.iter().copied().filter(|x| !names.contains(x)).collect();
+    unused.sort();
+    unused.dedup();
+    unknown.sort();
+    unknown.dedup();
+
+    let mut msg = Vec::new();
+    if !unused.is_empty() {
+        msg.push(format!("unused named param {}", unused.join(", ")));
+    }
+    if !unknown.is_empty() {
+        msg.push(format!("unknown named param {}", unknown.join(", ")));
+    }
+    if msg.is_empty() { Ok(()) } else { Err(msg.join(", ")) }
+}
+
+fn has_positional(item : &'_ PlanChain) -> bool {
+    item.args.iter().flatten().any(|x| x.name.is_none()) || item.bind.iter().flatten().any(|x| x.name.is_none())
+}
+
+impl<'c> ChainQuery<'c> {
+    pub fn new(item : &'_ PlanChain, ctx : &'c QueryRunContext) -> Result<Self, CommonError> {
+        let conn_info = GLOBAL.get_conn_info(item.connection.as_str().into()).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get conn info failed {}", item.id), e)
+        })?;
+
+        let names = get_declared_names(item);
+        if conn_info.conn_type == CONN_TYPE_REDIS {
+            if !names.is_empty() {
+                return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} redis not support named param", item.id)).to_result();
+            }
+            return Ok(ChainQuery { query : item.query.clone(), params : None, ctx });
+        }
+
+        let rewrite = rewrite_named_query(item.query.as_str(), conn_info.conn_type.as_str(), names.as_slice()).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ParsingFail, format!("named query parse failed {}", item.id), e)
+        })?;
+        if let Err(msg) = check_named_params(names.as_slice(), rewrite.as_ref().map(|(_, p)| p.as_slice())) {
+            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("{} {}", item.id, msg)).to_result();
+        }
+
+        match rewrite {
+            Some(_) if has_positional(item) => {
+                CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} named placeholder and positional idx can not be mixed", item.id)).to_result()
+            },
+            Some((query, params)) => Ok(ChainQuery { query, params : Some(params), ctx }),
+            None => Ok(ChainQuery { query : item.query.clone(), params : None, ctx })
+        }
+    }
+
+    pub fn bind_ids<'b>(&'b self, item : &'b PlanChain) -> Vec<&'b str> {
+        let mut ids : Vec<&'b str> = item.bind.as_ref().map_or(Vec::new(), |binds| {
+            binds.iter().map(|x| x.id.as_str()).collect()
+        });
+
+        if let Some(params) = self.params.as_ref() {
+            for p in params {
+                if let Placeholder::Ref(id, _) = p {
+                    ids.push(id.as_str());
+                }
+            }
+        }
+
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    fn create_named_value(&self, item : &'_ PlanChain, p : &'_ Placeholder, m : &HashMap<String, PairValueEnum>, bind_row : &'_ BindRow) -> Result<PairValueEnum, CommonError> {
+        match p {
+            Placeholder::Ref(id, key) => super::get_bind_value(m, id.as_str(), key.as_str(), bind_row.get(id.as_str())),
+            Placeholder::Name(name) => {
+                let arg = item.args.as_ref().and_then(|args| {
+                    args.iter().find(|x| x.name.as_ref() == Some(name))
+                });
+                if let Some(arg) = arg {
+                    return super::value::create_arg_value(arg, self.ctx);
+                }
+
+                let bind = item.bind.as_ref().and_then(|binds| {
+                    binds.iter().find(|x| x.name.as_ref() == Some(name))
+                });
+                if let Some(bind) = bind {
+                    let data = super::get_bind_value(m, bind.id.as_str(), bind.key.as_str(), bind.row.unwrap_or_else(|| bind_row.get(bind.id.as_str())))?;
+                    return match bind.cast.as_ref() {
+                        Some(c) => super::cast::cast_value(data, c.as_str()).map_err(|e| {
+                            CommonError::extend(&CommonDefaultErrorKind::NotMatchArgs, format!("{} bind :{} cast failed", item.id, name), e)
+                        }),
+                        None => Ok(data)
+                    };
+                }
+
+                CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} not exists named param :{}", item.id, name)).to_result()
+            }
+        }
+    }
+
+    pub fn create_bind(&self, item : &'_ PlanChain, m : &HashMap<String, PairValueEnum>, bind_row : &'_ BindRow) -> Result<Vec<PairValueEnum>, CommonError> {
+        match self.params.as_ref() {
+            None => super::create_query_bind_array(item, m, bind_row, self.ctx),
+            Some(params) => {
+                let mut v = Vec::with_capacity(params.len());
+                for p in params {
+                    v.push(self.create_named_value(item, p, m, bind_row).map_err(|e| {
+                        CommonError::extend(&CommonDefaultErrorKind::ParsingFail, "named param use failed", e)
+                    })?);
+                }
+                Ok(v)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mypip_global::constant::*;
+    use super::{check_named_params, rewrite_named_query, Placeholder};
+
+    #[test]
+    fn rewrite_declared_names_only() {
+        let (query, params) = rewrite_named_query("SELECT arr[1:n], :id, :id FROM t WHERE ts > :since", CONN_TYPE_PG, &["id"]).unwrap().unwrap();
+        assert_eq!(query, "SELECT arr[1:n], $1, $1 FROM t WHERE ts > :since");
+        assert_eq!(params, vec![Placeholder::Name(String::from("id"))]);
+    }
+
+    #[test]
+    fn rewrite_skip_comment_and_dollar_quote() {
+        let query = "SELECT :id -- :id in comment\n, /* :id */ $$ :id $$, $fn$ :id $fn$, ':id', $1::int";
+        let (ret, params) = rewrite_named_query(query, CONN_TYPE_SQLITE, &["id"]).unwrap().unwrap();
+        assert_eq!(ret, "SELECT ? -- :id in comment\n, /* :id */ $$ :id $$, $fn$ :id $fn$, ':id', $1::int");
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn rewrite_ref_placeholder() {
+        let (ret, params) = rewrite_named_query("INSERT INTO t VALUES ({{src.id}}, {{ src.name }})", CONN_TYPE_PG, &[]).unwrap().unwrap();
+        assert_eq!(ret, "INSERT INTO t VALUES ($1, $2)");
+        assert_eq!(params, vec![
+            Placeholder::Ref(String::from("src"), String::from("id")),
+            Placeholder::Ref(String::from("src"), String::from("name")),
+        ]);
+    }
+
+    #[test]
+    fn rewrite_none_without_placeholder() {
+        assert!(rewrite_named_query("SELECT a::text FROM t WHERE b = $1", CONN_TYPE_PG, &[]).unwrap().is_none());
+        assert!(rewrite_named_query("SELECT :a", CONN_TYPE_PG, &[]).unwrap().is_none());
+        assert!(rewrite_named_query("SELECT {{}}", CONN_TYPE_PG, &[]).is_err());
+    }
+
+    #[test]
+    fn named_params_unused_or_unknown_fail() {
+        let (_, params) = rewrite_named_query("SELECT :id, {{src.id}}", CONN_TYPE_PG, &["id", "since"]).unwrap().unwrap();
+        assert_eq!(check_named_params(&["id", "since"], Some(params.as_slice())).unwrap_err(), "unused named param since");
+        assert!(check_named_params(&["id"], Some(params.as_slice())).is_ok());
+
+        let (_, params) = rewrite_named_query("SELECT {{id}}, {{name}}", CONN_TYPE_PG, &["id"]).unwrap().unwrap();
+        assert_eq!(check_named_params(&["id"], Some(params.as_slice())).unwrap_err(), "unknown named param name");
+
+        assert!(rewrite_named_query("SELECT 1", CONN_TYPE_PG, &["id"]).unwrap().is_none());
+        assert_eq!(check_named_params(&["id"], None).unwrap_err(), "unused named param id");
+        assert!(check_named_params(&[], None).is_ok());
+    }
+}
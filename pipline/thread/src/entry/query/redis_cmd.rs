@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use common_rs::logger::*;
+use mypip_global::constant::*;
+use mypip_global::GLOBAL;
+use mypip_types::config::plan::{PlanChain, PlanRedis};
+use mypip_types::interface::GlobalLayout;
+use crate::types::QueryRunContext;
+use super::merge::BindResultMerger;
+use super::row_error::RowErrorHandler;
+
+// one row per KEYS entry, ARGV holds per row : arg count, command, args after the key, expire ttl
+const REDIS_PIPELINE_SCRIPT : &'static str = r#"
+local ret = {0, ''}
+local i = 1
+for k = 1, #KEYS do
+    local n = tonumber(ARGV[i])
+    local cmd = {ARGV[i + 1], KEYS[k]}
+    for j = 1, n do
+        cmd[j + 2] = ARGV[i + 1 + j]
+    end
+    local ttl = ARGV[i + n + 2]
+    local r = redis.pcall(unpack(cmd))
+    if type(r) == 'table' and r.err then
+        ret[2] = r.err
+        return ret
+    end
+    if ttl ~= '' then
+        local e = redis.pcall('EXPIRE', KEYS[k], ttl)
+        if type(e) == 'table' and e.err then
+            ret[2] = e.err
+            return ret
+        end
+    end
+    ret[1] = ret[1] + 1
+    ret[#ret + 1] = r
+    i = i + n + 3
+end
+return ret
+"#;
+
+pub(super) struct RedisPipelineRet {
+    pub applied : usize,
+    pub err : Option<String>,
+    pub rets : Vec<PairValueEnum>,
+}
+
+enum KeyPart {
+    Text(String),
+    Col(String),
+}
+
+struct RedisRows<'d> {
+    cols : &'d HashMap<String, PairValueEnum>,
+    rows : usize,
+}
+
+impl<'d> RedisRows<'d> {
+    fn get(&self, col : &'_ str, row : usize) -> Result<&'d PairValueEnum, CommonError> {
+        match self.cols.get(col) {
+            Some(PairValueEnum::Array(arr)) => Ok(arr.get(row).unwrap_or(&PairValueEnum::Null)),
+            Some(other) => Ok(other),
+            None => CommonError::new(&CommonDefaultErrorKind::NoData, format!("redis column {} not exists", col)).to_result()
+        }
+    }
+}
+
+fn parse_key_template(key : &'_ str) -> Result<Vec<KeyPart>, CommonError> {
+    let mut parts = Vec::new();
+    let mut rest = key;
+
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').map(|x| x + start).ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("unterminated redis key column {}", key))
+        })?;
+        if start > 0 {
+            parts.push(KeyPart::Text(rest[..start].to_string()));
+        }
+        parts.push(KeyPart::Col(rest[start + 1..end].trim().to_string()));
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(KeyPart::Text(rest.to_string()));
+    }
+
+    Ok(parts)
+}
+
+fn convert_key_value(v : &'_ PairValueEnum) -> Result<String, CommonError> {
+    match v {
+        PairValueEnum::Int(x) => Ok(x.to_string()),
+        PairValueEnum::BigInt(x) => Ok(x.to_string()),
+        PairValueEnum::Double(x) => Ok(x.to_string()),
+        PairValueEnum::Float(x) => Ok(x.to_string()),
+        PairValueEnum::Bool(x) => Ok(x.to_string()),
+        PairValueEnum::String(x) => Ok(x.clone()),
+        other => CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("redis key not support {:?}", other)).to_result()
+    }
+}
+
+fn create_key(parts : &'_ [KeyPart], data : &'_ RedisRows<'_>, row : usize) -> Result<String, CommonError> {
+    let mut key = String::new();
+    for part in parts {
+        match part {
+            KeyPart::Text(t) => key.push_str(t.as_str()),
+            KeyPart::Col(col) => key.push_str(convert_key_value(data.get(col.as_str(), row)?)?.as_str())
+        }
+    }
+    Ok(key)
+}
+
+fn create_field_args(rule : &'_ PlanRedis, data : &'_ RedisRows<'_>, row : usize, v : &mut Vec<PairValueEnum>) -> Result<(), CommonError> {
+    let fields = rule.fields.as_ref().filter(|x| !x.is_empty()).ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("redis {} need fields", rule.command))
+    })?;
+
+    for f in fields {
+        v.push(PairValueEnum::String(f.field.clone()));
+        v.push(data.get(f.column.as_str(), row)?.clone());
+    }
+    Ok(())
+}
+
+fn create_value_arg(rule : &'_ PlanRedis, data : &'_ RedisRows<'_>, row : usize) -> Result<PairValueEnum, CommonError> {
+    let col = rule.value.as_ref().ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("redis {} need value column", rule.command))
+    })?;
+    Ok(data.get(col.as_str(), row)?.clone())
+}
+
+fn create_command_args(rule : &'_ PlanRedis, command : &'_ str, key : String, data : &'_ RedisRows<'_>, row : usize) -> Result<Vec<PairValueEnum>, CommonError> {
+    let mut v = vec![PairValueEnum::String(key)];
+
+    match command {
+        "SET" => {
+            v.push(create_value_arg(rule, data, row)?);
+            if let Some(ttl) = rule.ttl_sec {
+                v.push(PairValueEnum::String(String::from("EX")));
+                v.push(PairValueEnum::String(ttl.to_string()));
+            }
+        },
+        "HSET" => create_field_args(rule, data, row, &mut v)?,
+        "XADD" => {
+            v.push(PairValueEnum::String(String::from("*")));
+            create_field_args(rule, data, row, &mut v)?;
+        },
+        "LPUSH" | "RPUSH" | "SADD" => v.push(create_value_arg(rule, data, row)?),
+        "DEL" => {},
+        other => {
+            return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support redis command {}", other)).to_result();
+        }
+    }
+
+    Ok(v)
+}
+
+fn create_pipeline_param(sha : &'_ str, command : &'_ str, rows : &'_ [Vec<PairValueEnum>], ttl : Option<u64>) -> Vec<PairValueEnum> {
+    let mut params = Vec::with_capacity(2 + rows.iter().map(|x| x.len() + 3).sum::<usize>());
+    params.push(PairValueEnum::String(sha.to_string()));
+    params.push(PairValueEnum::String(rows.len().to_string()));
+    params.extend(rows.iter().map(|x| x[0].clone()));
+
+    for row in rows {
+        params.push(PairValueEnum::String((row.len() - 1).to_string()));
+        params.push(PairValueEnum::String(command.to_string()));
+        params.extend(row[1..].iter().cloned());
+        params.push(PairValueEnum::String(ttl.map_or(String::new(), |x| x.to_string())));
+    }
+    params
+}
+
+fn convert_count(v : &'_ PairValueEnum) -> Option<usize> {
+    match v {
+        PairValueEnum::Int(x) => usize::try_from(*x).ok(),
+        PairValueEnum::BigInt(x) => usize::try_from(*x).ok(),
+        PairValueEnum::String(x) => x.parse().ok(),
+        _ => None
+    }
+}
+
+fn parse_pipeline_ret(ret : PairValueEnum, rows : usize) -> Result<RedisPipelineRet, CommonError> {
+    match ret {
+        PairValueEnum::Array(mut v) if v.len() >= 2 => {
+            let rets = v.split_off(2);
+            let applied = convert_count(&v[0]).filter(|x| *x <= rows).ok_or_else(|| {
+                CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("redis pipeline applied count broken {:?}", v[0]))
+            })?;
+            let err = match &v[1] {
+                PairValueEnum::String(e) if !e.is_empty() => Some(e.clone()),
+                _ => None
+            };
+            Ok(RedisPipelineRet { applied, err, rets })
+        },
+        other => CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("redis pipeline reply broken {:?}", other)).to_result()
+    }
+}
+
+// the script is loaded once per step and each chunk runs by EVALSHA, keys go through KEYS so redis knows what the script touches
+pub(super) struct RedisPipeline {
+    command : String,
+    ttl : Option<u64>,
+    sha : Option<String>,
+}
+
+impl RedisPipeline {
+    pub fn new(command : &'_ str, ttl : Option<u64>) -> Self {
+        RedisPipeline { command : command.to_string(), ttl, sha : None }
+    }
+
+    fn load(&mut self, item : &'_ PlanChain, runner : &mut super::ChainRunner<'_>) -> Result<String, CommonError> {
+        let load_param = vec![PairValueEnum::String(String::from("LOAD")), PairValueEnum::String(REDIS_PIPELINE_SCRIPT.to_string())];
+        match runner(item, "SCRIPT", vec![load_param])?.pop().unwrap_or(PairValueEnum::Null) {
+            PairValueEnum::String(sha) if !sha.is_empty() => {
+                self.sha = Some(sha.clone());
+                Ok(sha)
+            },
+            other => CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("redis script load reply broken {:?}", other)).to_result()
+        }
+    }
+
+    pub fn run(&mut self, item : &'_ PlanChain, rows : &'_ [Vec<PairValueEnum>], runner : &mut super::ChainRunner<'_>) -> Result<RedisPipelineRet, CommonError> {
+        if let Some(pos) = rows.iter().position(|x| x.is_empty()) {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("redis {} row {} need key", self.command, pos)).to_result();
+        }
+
+        let sha = match self.sha.clone() {
+            Some(sha) => sha,
+            None => self.load(item, runner)?
+        };
+        let ret = match runner(item, "EVALSHA", vec![create_pipeline_param(sha.as_str(), self.command.as_str(), rows, self.ttl)]) {
+            // script cache flushed or failed over since the load, load again once
+            Err(e) if e.to_string().contains("NOSCRIPT") => {
+                let sha = self.load(item, runner)?;
+                runner(item, "EVALSHA", vec![create_pipeline_param(sha.as_str(), self.command.as_str(), rows, self.ttl)])?
+            },
+            ret => ret?
+        };
+
+        parse_pipeline_ret(ret.into_iter().next().unwrap_or(PairValueEnum::Null), rows.len())
+    }
+
+    // a failed row goes to on_row_error, the rows after it in the chunk run again
+    pub fn run_rows(&mut self, item : &'_ PlanChain, plan_name : &'_ str, rows : &'_ [Vec<PairValueEnum>], row_err : &mut RowErrorHandler<'_>, merger : &mut BindResultMerger, runner : &mut super::ChainRunner<'_>) -> Result<(), CommonError> {
+        let mut pos = 0;
+
+        while pos < rows.len() {
+            log_debug!(plan_name, "redis pipeline running, name={} command={} rows={}", item.id.as_str(), self.command.as_str(), rows.len() - pos);
+            let ret = self.run(item, &rows[pos..], runner).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("redis pipeline failed {}:{}", plan_name, item.id), e)
+            });
+
+            let ret = match ret {
+                Ok(ret) => ret,
+                Err(e) if row_err.is_abort() => return Err(e),
+                Err(e) => {
+                    log_error!(plan_name, "{}, rows not confirmed", e);
+                    for row in rows[pos..].iter() {
+                        row_err.handle(row.as_slice(), CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("redis pipeline failed {}:{}, {}", plan_name, item.id, e)))?;
+                    }
+                    return Ok(());
+                }
+            };
+
+            row_err.success(ret.applied);
+            for one in ret.rets {
+                merger.push(one, None)?;
+            }
+            match ret.err {
+                Some(err) => {
+                    let failed = pos + ret.applied;
+                    row_err.handle(rows.get(failed).map_or(&[][..], |x| x.as_slice()), CommonError::new(&CommonDefaultErrorKind::ExecuteFail, format!("redis {} failed {}:{}, {}", self.command, plan_name, item.id, err)))?;
+                    pos = failed + 1;
+                },
+                None => pos = rows.len()
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn run_redis_chain(item : &'_ PlanChain, ctx : &'_ QueryRunContext, data_map : &HashMap<String, PairValueEnum>, is_tx : bool, runner : &mut super::ChainRunner<'_>) -> Result<PairValueEnum, CommonError> {
+    let rule = item.redis.as_ref().ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} not exists redis", item.id))
+    })?;
+
+    let conn_info = GLOBAL.get_conn_info(item.connection.as_str().into()).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get conn info failed {}:{}", ctx.plan_name, item.id), e)
+    })?;
+    if conn_info.conn_type != CONN_TYPE_REDIS {
+        return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} redis step need redis connection, got {}", item.id, conn_info.conn_type)).to_result();
+    }
+
+    let pipeline = rule.pipeline.unwrap_or(crate::constant::REDIS_DEFAULT_PIPELINE);
+    if pipeline == 0 {
+        return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} pipeline must be > 0", item.id)).to_result();
+    }
+
+    let data = match data_map.get(rule.step.as_str()) {
+        Some(v @ PairValueEnum::Map(cols)) => RedisRows { cols, rows : super::get_ret_bind_param_cnt(v) },
+        Some(PairValueEnum::Null) => return Ok(PairValueEnum::Null),
+        _ => {
+            return CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} redis step {} not exists columnar data", item.id, rule.step)).to_result();
+        }
+    };
+
+    let template = super::value::resolve_dynamic_value(rule.key.as_str(), ctx)?.unwrap_or_default();
+    let key_parts = parse_key_template(template.as_str())?;
+    let command = rule.command.to_uppercase();
+    let expire_ttl = rule.ttl_sec.filter(|_| command != "SET" && command != "DEL");
+
+    let mut row_err = RowErrorHandler::new(item, ctx, is_tx)?;
+    let mut redis_pipeline = RedisPipeline::new(command.as_str(), expire_ttl);
+    let mut merger = BindResultMerger::default();
+    let mut start = 0;
+
+    while start < data.rows {
+        let end = (start + pipeline).min(data.rows);
+        let mut cmd_rows = Vec::with_capacity(end - start);
+
+        for row in start..end {
+            let cmd = create_key(key_parts.as_slice(), &data, row).and_then(|key| {
+                create_command_args(rule, command.as_str(), key, &data, row)
+            });
+            match cmd {
+                Ok(cmd) => cmd_rows.push(cmd),
+                Err(e) => {
+                    row_err.handle(&[], CommonError::extend(&CommonDefaultErrorKind::ParsingFail, format!("{} redis command failed row {}", item.id, row), e))?;
+                }
+            }
+        }
+
+        redis_pipeline.run_rows(item, ctx.plan_name.as_str(), cmd_rows.as_slice(), &mut row_err, &mut merger, runner).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("redis {} failed {}:{} rows {}..{}", command, ctx.plan_name, item.id, start, end), e)
+        })?;
+        start = end;
+    }
+
+    row_err.finish();
+    Ok(merger.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(v : &'_ PairValueEnum) -> &'_ str {
+        match v {
+            PairValueEnum::String(x) => x.as_str(),
+            other => panic!("not string {:?}", other)
+        }
+    }
+
+    #[test]
+    fn pipeline_keys_are_declared() {
+        let rows = vec![
+            vec![PairValueEnum::String(String::from("k:1")), PairValueEnum::String(String::from("a"))],
+            vec![PairValueEnum::String(String::from("k:2"))],
+        ];
+        let params = create_pipeline_param("sha", "SET", rows.as_slice(), Some(60));
+        let v : Vec<&str> = params.iter().map(text).collect();
+
+        assert_eq!(v, vec!["sha", "2", "k:1", "k:2", "1", "SET", "a", "60", "0", "SET", "60"]);
+    }
+
+    #[test]
+    fn pipeline_reply_parse() {
+        let ret = PairValueEnum::Array(vec![PairValueEnum::BigInt(1), PairValueEnum::String(String::from("WRONGTYPE")), PairValueEnum::String(String::from("OK"))]);
+        let ret = parse_pipeline_ret(ret, 2).unwrap();
+        assert_eq!(ret.applied, 1);
+        assert_eq!(ret.err.as_deref(), Some("WRONGTYPE"));
+        assert_eq!(ret.rets.len(), 1);
+
+        let over = PairValueEnum::Array(vec![PairValueEnum::BigInt(3), PairValueEnum::String(String::new())]);
+        assert!(parse_pipeline_ret(over, 2).is_err());
+        assert!(parse_pipeline_ret(PairValueEnum::Null, 2).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use common_rs::logger::*;
+use mypip_global::constant::*;
+use mypip_global::GLOBAL;
+use mypip_types::config::plan::{PlanChain, PlanDeadLetter};
+use mypip_types::interface::GlobalLayout;
+use crate::constant::*;
+use crate::types::QueryRunContext;
+
+#[derive(PartialEq, Eq)]
+enum RowErrorPolicy {
+    Abort,
+    Skip,
+    DeadLetter,
+}
+
+pub(super) struct RowErrorHandler<'h> {
+    item : &'h PlanChain,
+    ctx : &'h QueryRunContext,
+    policy : RowErrorPolicy,
+    ok : usize,
+    skipped : usize,
+    dead : usize,
+}
+
+fn create_insert_query(conn_type : &'_ str, table : &'_ str) -> String {
+    let params : Vec<String> = match conn_type {
+        CONN_TYPE_PG => (1..=6).map(|x| format!("${}", x)).collect(),
+        _ => (1..=6).map(|_| String::from("?")).collect()
+    };
+    format!("INSERT INTO {} (plan_name, step_id, run_id, bind_values, error, created_at) VALUES ({})", table, params.join(", "))
+}
+
+impl<'h> RowErrorHandler<'h> {
+    pub fn new(item : &'h PlanChain, ctx : &'h QueryRunContext, is_tx : bool) -> Result<Self, CommonError> {
+        let policy = match item.on_row_error.as_ref().map(|x| x.as_str()) {
+            None | Some(ROW_ERROR_ABORT) => RowErrorPolicy::Abort,
+            Some(ROW_ERROR_SKIP) => RowErrorPolicy::Skip,
+            Some(ROW_ERROR_DEAD_LETTER) => RowErrorPolicy::DeadLetter,
+            Some(other) => {
+                return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} not support on_row_error {}", item.id, other)).to_result();
+            }
+        };
+
+        if policy != RowErrorPolicy::Abort && is_tx {
+            return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} on_row_error not support transaction chain", item.id)).to_result();
+        }
+        if policy == RowErrorPolicy::DeadLetter {
+            match item.dead_letter.as_ref() {
+                Some(PlanDeadLetter { connection : Some(_), table : Some(_), .. }) | Some(PlanDeadLetter { path : Some(_), .. }) => {},
+                _ => {
+                    return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} dead_letter need connection and table, or path", item.id)).to_result();
+                }
+            }
+        }
+
+        Ok(RowErrorHandler { item, ctx, policy, ok : 0, skipped : 0, dead : 0 })
+    }
+
+    pub fn is_abort(&self) -> bool {
+        self.policy == RowErrorPolicy::Abort
+    }
+
+    pub fn success(&mut self, rows : usize) {
+        self.ok += rows;
+    }
+
+    fn write_file(&self, path : &'_ str, line : String) -> Result<(), CommonError> {
+        let io_err = |e : std::io::Error| CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("dead letter write {} failed : {}", path, e));
+
+        if let Some(parent) = Path::new(path).parent().filter(|x| !x.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(io_err)?;
+        }
+        let mut f = OpenOptions::new().create(true).append(true).open(path).map_err(io_err)?;
+        writeln!(f, "{}", line).map_err(io_err)
+    }
+
+    fn write_table(&self, connection : &'_ str, table : &'_ str, bind : &'_ [PairValueEnum], err : &'_ CommonError) -> Result<(), CommonError> {
+        let conn_info = GLOBAL.get_conn_info(connection.into()).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get conn info failed {}", connection), e)
+        })?;
+        let _slot = super::ConnSlot::acquire(connection).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("acquire connection failed {}", connection), e)
+        })?;
+        let p = GLOBAL.get_exec_pool(connection.into()).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get pool failed {}", connection), e)
+        })?;
+        let mut p_item = p.get_owned(()).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::InvalidApiCall, format!("get pool item failed {}", connection), e)
+        })?;
+
+        let params = vec![
+            PairValueEnum::String(self.ctx.plan_name.clone()),
+            PairValueEnum::String(self.item.id.clone()),
+            PairValueEnum::String(self.ctx.run_id.clone()),
+            PairValueEnum::String(serde_json::Value::Array(bind.iter().map(super::sink::convert_json_value).collect()).to_string()),
+            PairValueEnum::String(err.to_string()),
+            PairValueEnum::BigInt(self.ctx.scheduled_time as i64),
+        ];
+
+        let query = create_insert_query(conn_info.conn_type.as_str(), table);
+        match p_item.get_value().execute_pair(query.as_str(), &PairValueEnum::Array(params)) {
+            Ok(_) => {
+                p_item.restoration();
+                Ok(())
+            },
+            Err(e) => {
+                p_item.dispose();
+                CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("dead letter insert failed {}", table), e).to_result()
+            }
+        }
+    }
+
+    fn write_dead_letter(&self, bind : &'_ [PairValueEnum], err : &'_ CommonError) -> Result<(), CommonError> {
+        let dl = self.item.dead_letter.as_ref().ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} not exists dead_letter", self.item.id))
+        })?;
+
+        if let (Some(connection), Some(table)) = (dl.connection.as_ref(), dl.table.as_ref()) {
+            return self.write_table(connection.as_str(), table.as_str(), bind, err);
+        }
+
+        let path = dl.path.as_ref().map(|x| super::value::resolve_dynamic_value(x.as_str(), self.ctx)).transpose()?.flatten().unwrap_or_default();
+        let line = serde_json::json!({
+            "plan_name" : self.ctx.plan_name,
+            "step_id" : self.item.id,
+            "run_id" : self.ctx.run_id,
+            "bind_values" : bind.iter().map(super::sink::convert_json_value).collect::<Vec<serde_json::Value>>(),
+            "error" : err.to_string(),
+            "created_at" : self.ctx.scheduled_time as i64,
+        });
+        self.write_file(path.as_str(), line.to_string())
+    }
+
+    pub fn handle(&mut self, bind : &'_ [PairValueEnum], err : CommonError) -> Result<(), CommonError> {
+        match self.policy {
+            RowErrorPolicy::Abort => Err(err),
+            RowErrorPolicy::Skip => {
+                log_error!(self.ctx.plan_name.as_str(), "skip row {}, {}", self.item.id, err);
+                self.skipped += 1;
+                Ok(())
+            },
+            RowErrorPolicy::DeadLetter => {
+                self.write_dead_letter(bind, &err).map_err(|e| {
+                    CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("{} dead letter failed, row error : {}", self.item.id, err), e)
+                })?;
+                self.dead += 1;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn finish(&self) {
+        if self.skipped > 0 || self.dead > 0 {
+            log_info!(self.ctx.plan_name.as_str(), "row error summary, name={} ok={} skipped={} dead_letter={}", self.item.id, self.ok, self.skipped, self.dead);
+        }
+    }
+}
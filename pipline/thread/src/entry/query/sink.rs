@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use base64::Engine;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use common_rs::logger::*;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use mypip_types::config::plan::{PlanChain, PlanSink};
+use crate::types::QueryRunContext;
+
+const SINK_PART_KEY : &'static str = "${part}";
+const SINK_PAGE_KEY : &'static str = "${page}";
+
+pub(super) fn convert_json_value(v : &'_ PairValueEnum) -> serde_json::Value {
+    match v {
+        PairValueEnum::Null => serde_json::Value::Null,
+        PairValueEnum::Int(x) => serde_json::Value::from(*x),
+        PairValueEnum::BigInt(x) => serde_json::Value::from(*x),
+        PairValueEnum::Double(x) => serde_json::Value::from(*x),
+        PairValueEnum::Float(x) => serde_json::Value::from(*x),
+        PairValueEnum::Bool(x) => serde_json::Value::from(*x),
+        PairValueEnum::String(x) => serde_json::Value::from(x.as_str()),
+        PairValueEnum::Bin(x) => serde_json::Value::from(base64::engine::general_purpose::STANDARD.encode(x)),
+        PairValueEnum::Array(arr) => serde_json::Value::Array(arr.iter().map(convert_json_value).collect()),
+        PairValueEnum::Map(map) => serde_json::Value::Object(map.iter().map(|(k, v)| (k.clone(), convert_json_value(v))).collect()),
+    }
+}
+
+fn convert_csv_field(v : &'_ PairValueEnum) -> String {
+    let s = match v {
+        PairValueEnum::Null => return String::new(),
+        PairValueEnum::String(x) => x.clone(),
+        PairValueEnum::Bin(x) => base64::engine::general_purpose::STANDARD.encode(x),
+        other => convert_json_value(other).to_string()
+    };
+
+    if s.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s
+    }
+}
+
+struct SinkTable<'d> {
+    cols : Vec<String>,
+    values : Vec<Option<&'d Vec<PairValueEnum>>>,
+    rows : usize,
+}
+
+impl<'d> SinkTable<'d> {
+    fn new(sink : &'_ PlanSink, data : &'d PairValueEnum) -> Result<Self, CommonError> {
+        let map = match data {
+            PairValueEnum::Map(map) => map,
+            PairValueEnum::Null => return Ok(SinkTable { cols : Vec::new(), values : Vec::new(), rows : 0 }),
+            other => {
+                return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("sink step {} not columnar {:?}", sink.step, other)).to_result();
+            }
+        };
+
+        let cols = match sink.columns.as_ref() {
+            Some(cols) => cols.clone(),
+            None => {
+                let mut cols : Vec<String> = map.keys().cloned().collect();
+                cols.sort();
+                cols
+            }
+        };
+
+        let mut values = Vec::with_capacity(cols.len());
+        for col in cols.iter() {
+            match map.get(col) {
+                Some(PairValueEnum::Array(arr)) => values.push(Some(arr)),
+                Some(_) | None if sink.columns.is_some() => {
+                    return CommonError::new(&CommonDefaultErrorKind::NoData, format!("sink column {}.{} not exists", sink.step, col)).to_result();
+                },
+                _ => values.push(None)
+            }
+        }
+
+        let rows = values.iter().map(|x| x.map_or(0, |arr| arr.len())).max().unwrap_or(0);
+        Ok(SinkTable { cols, values, rows })
+    }
+
+    fn get(&self, col : usize, row : usize) -> &'_ PairValueEnum {
+        self.values[col].and_then(|arr| arr.get(row)).unwrap_or(&PairValueEnum::Null)
+    }
+
+    fn write_csv(&self, w : &mut dyn Write, start : usize, end : usize) -> std::io::Result<()> {
+        writeln!(w, "{}", self.cols.iter().map(|x| convert_csv_field(&PairValueEnum::String(x.clone()))).collect::<Vec<String>>().join(","))?;
+        for row in start..end {
+            let line : Vec<String> = (0..self.cols.len()).map(|col| convert_csv_field(self.get(col, row))).collect();
+            writeln!(w, "{}", line.join(","))?;
+        }
+        Ok(())
+    }
+
+    fn write_jsonl(&self, w : &mut dyn Write, start : usize, end : usize) -> std::io::Result<()> {
+        for row in start..end {
+            let obj : serde_json::Map<String, serde_json::Value> = self.cols.iter().enumerate().map(|(col, name)| {
+                (name.clone(), convert_json_value(self.get(col, row)))
+            }).collect();
+            writeln!(w, "{}", serde_json::Value::Object(obj))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_sink_data(sink : &'_ PlanSink, table : &'_ SinkTable<'_>, w : &mut dyn Write, start : usize, end : usize) -> Result<(), CommonError> {
+    match sink.format.as_str() {
+        crate::constant::SINK_FORMAT_CSV => table.write_csv(w, start, end),
+        crate::constant::SINK_FORMAT_JSONL => table.write_jsonl(w, start, end),
+        other => return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support sink format {}", other)).to_result()
+    }.map_err(|e| CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("sink data write failed : {}", e)))
+}
+
+fn write_sink_file(sink : &'_ PlanSink, table : &'_ SinkTable<'_>, path : &'_ str, start : usize, end : usize) -> Result<(), CommonError> {
+    let io_err = |e : std::io::Error| CommonError::new(&CommonDefaultErrorKind::SystemCallFail, format!("sink write {} failed : {}", path, e));
+
+    if let Some(parent) = Path::new(path).parent().filter(|x| !x.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(io_err)?;
+    }
+
+    let tmp = format!("{}.tmp", path);
+    let mut file = BufWriter::new(File::create(tmp.as_str()).map_err(io_err)?);
+
+    let ret = if sink.gzip {
+        let mut gz = GzEncoder::new(&mut file, Compression::default());
+        write_sink_data(sink, table, &mut gz, start, end).and_then(|_| gz.finish().map(|_| ()).map_err(io_err))
+    } else {
+        write_sink_data(sink, table, &mut file, start, end)
+    }.and_then(|_| file.flush().map_err(io_err));
+    drop(file);
+
+    if let Err(e) = ret {
+        let _ = std::fs::remove_file(tmp.as_str());
+        return Err(e);
+    }
+    std::fs::rename(tmp.as_str(), path).map_err(io_err)
+}
+
+pub(super) fn run_sink_chain(item : &'_ PlanChain, ctx : &'_ QueryRunContext, page : Option<usize>, data_map : &HashMap<String, PairValueEnum>) -> Result<PairValueEnum, CommonError> {
+    let sink = item.sink.as_ref().ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} not exists sink", item.id))
+    })?;
+
+    let data = data_map.get(sink.step.as_str()).ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} sink step {} not exists data", item.id, sink.step))
+    })?;
+    if sink.format != crate::constant::SINK_FORMAT_CSV && sink.format != crate::constant::SINK_FORMAT_JSONL {
+        return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} not support sink format {}", item.id, sink.format)).to_result();
+    }
+    // a paginated chain runs the sink once per page, a fixed path would keep only the last page
+    if page.is_some() && !sink.path.contains(SINK_PAGE_KEY) {
+        return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} sink in paginated chain need {} in path", item.id, SINK_PAGE_KEY)).to_result();
+    }
+    let table = SinkTable::new(sink, data)?;
+
+    let part_rows = match sink.rotate_rows {
+        Some(0) => {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} rotate_rows must be > 0", item.id)).to_result();
+        },
+        Some(n) if !sink.path.contains(SINK_PART_KEY) => {
+            return CommonError::new(&CommonDefaultErrorKind::InvalidApiCall, format!("{} rotate_rows {} need {} in path", item.id, n, SINK_PART_KEY)).to_result();
+        },
+        Some(n) => n,
+        None => table.rows.max(1)
+    };
+
+    let mut files = Vec::new();
+    let mut rows = Vec::new();
+    let mut start = 0;
+
+    loop {
+        let end = (start + part_rows).min(table.rows);
+        let template = sink.path.replace(SINK_PART_KEY, files.len().to_string().as_str()).replace(SINK_PAGE_KEY, page.unwrap_or(0).to_string().as_str());
+        let path = super::value::resolve_dynamic_value(template.as_str(), ctx).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ParsingFail, format!("{} sink path failed {}", item.id, sink.path), e)
+        })?.unwrap_or_default();
+
+        write_sink_file(sink, &table, path.as_str(), start, end).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("{} sink failed", item.id), e)
+        })?;
+        log_debug!(ctx.plan_name.as_str(), "sink written, name={} path={} rows={}", item.id.as_str(), path.as_str(), end - start);
+
+        files.push(PairValueEnum::String(path));
+        rows.push(PairValueEnum::BigInt((end - start) as i64));
+        start = end;
+        if start >= table.rows {
+            break;
+        }
+    }
+
+    let mut ret = HashMap::new();
+    ret.insert(String::from("file"), PairValueEnum::Array(files));
+    ret.insert(String::from("rows"), PairValueEnum::Array(rows));
+    Ok(PairValueEnum::Map(ret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_ctx() -> QueryRunContext {
+        QueryRunContext {
+            plan_name : String::from("sink_test"),
+            run_id : String::from("sink_test-0-0"),
+            scheduled_time : 0,
+            last_success_time : None,
+            watermark : None,
+        }
+    }
+
+    fn create_item(path : &'_ str) -> PlanChain {
+        let mut item = PlanChain::default();
+        item.id = String::from("out");
+        item.sink = Some(PlanSink { step : String::from("src"), format : String::from("jsonl"), path : path.to_string(), columns : None, gzip : false, rotate_rows : None });
+        item
+    }
+
+    fn create_page(ids : &'_ [i64]) -> HashMap<String, PairValueEnum> {
+        let mut cols = HashMap::new();
+        cols.insert(String::from("id"), PairValueEnum::Array(ids.iter().map(|x| PairValueEnum::BigInt(*x)).collect()));
+        let mut data_map = HashMap::new();
+        data_map.insert(String::from("src"), PairValueEnum::Map(cols));
+        data_map
+    }
+
+    #[test]
+    fn paginated_sink_writes_each_page() {
+        let dir = std::env::temp_dir().join(format!("mypip_sink_page_{}", std::process::id()));
+        let path = format!("{}/out_${{page}}.jsonl", dir.display());
+        let item = create_item(path.as_str());
+        let ctx = create_ctx();
+
+        run_sink_chain(&item, &ctx, Some(0), &create_page(&[1, 2])).unwrap();
+        run_sink_chain(&item, &ctx, Some(1), &create_page(&[3])).unwrap();
+
+        let page0 = std::fs::read_to_string(dir.join("out_0.jsonl")).unwrap();
+        let page1 = std::fs::read_to_string(dir.join("out_1.jsonl")).unwrap();
+        assert_eq!(page0.lines().count(), 2);
+        assert_eq!(page1.lines().collect::<Vec<&str>>(), vec![r#"{"id":3}"#]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn paginated_sink_need_page_key() {
+        let dir = std::env::temp_dir().join(format!("mypip_sink_fixed_{}", std::process::id()));
+        let item = create_item(format!("{}/out.jsonl", dir.display()).as_str());
+
+        assert!(run_sink_chain(&item, &create_ctx(), Some(0), &create_page(&[1])).is_err());
+        assert!(!dir.exists());
+    }
+}
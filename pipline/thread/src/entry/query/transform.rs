@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use mypip_global::GLOBAL;
+use mypip_types::config::plan::PlanChain;
+use mypip_types::interface::GlobalLayout;
+
+pub(super) fn run_script_chain(item : &'_ PlanChain, plan_name : &'_ str, data_map : &HashMap<String, PairValueEnum>) -> Result<PairValueEnum, CommonError> {
+    let script = item.script.as_ref().ok_or_else(|| {
+        CommonError::new(&CommonDefaultErrorKind::NoData, format!("{} not exists script", item.id))
+    })?;
+    let func = item.func.as_ref().map_or(crate::constant::CHAIN_SCRIPT_DEFAULT_FUNC, |x| x.as_str());
+
+    let p = GLOBAL.get_interpreter_pool(script.lang.as_str().into()).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::Etc, format!("{} - failed get pool {}", plan_name, script.lang), e)
+    })?;
+
+    let mut p_item = p.get_owned(()).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::Etc, format!("{} - failed get item", plan_name), e)
+    })?;
+
+    let vm = p_item.get_value();
+    vm.transform(script.file.as_str(), func, &PairValueEnum::Map(data_map.clone())).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::ExecuteFail, format!("{} - failed transform {}:{}", plan_name, item.id, func), e)
+    })
+}
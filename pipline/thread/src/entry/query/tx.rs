@@ -0,0 +1,20 @@
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use mypip_global::constant::*;
+
+pub(super) enum TxQuery {
+    Begin,
+    Commit,
+    Rollback,
+}
+
+pub(super) fn get_tx_query(conn_type : &'_ str, q : TxQuery) -> Result<&'static str, CommonError> {
+    match conn_type {
+        CONN_TYPE_PG | CONN_TYPE_DUCKDB | CONN_TYPE_SQLITE => Ok(match q {
+            TxQuery::Begin => "BEGIN",
+            TxQuery::Commit => "COMMIT",
+            TxQuery::Rollback => "ROLLBACK",
+        }),
+        _ => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("{} not support transaction", conn_type)).to_result()
+    }
+}
@@ -0,0 +1,298 @@
+use base64::Engine;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use mypip_types::config::plan::PlanChainArgs;
+use crate::types::QueryRunContext;
+
+fn get_now_millis() -> Result<u128, CommonError> {
+    Ok(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_err(|e| {
+        CommonError::new(&CommonDefaultErrorKind::SystemCallFail, e.to_string())
+    })?.as_millis())
+}
+
+fn civil_from_days(days : i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (if month <= 2 { yoe + era * 400 + 1 } else { yoe + era * 400 }, month, day)
+}
+
+fn format_date(millis : u128) -> String {
+    let (y, m, d) = civil_from_days((millis / 86_400_000) as i64);
+    format!("{:04}{:02}{:02}", y, m, d)
+}
+
+fn resolve_dynamic_key(key : &'_ str, ctx : &'_ QueryRunContext) -> Result<Option<String>, CommonError> {
+    if let Some(env_key) = key.strip_prefix("env:") {
+        return std::env::var(env_key).map(Some).map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::NoData, format!("env {} : {}", env_key, e))
+        });
+    }
+
+    match key {
+        "now" => Ok(Some(get_now_millis()?.to_string())),
+        "scheduled_time" => Ok(Some(ctx.scheduled_time.to_string())),
+        "date" => Ok(Some(format_date(ctx.scheduled_time))),
+        "plan_name" => Ok(Some(ctx.plan_name.clone())),
+        "run_id" => Ok(Some(ctx.run_id.clone())),
+        // restored from the watermark store when the plan has a watermark, otherwise empty after a restart or reload
+        "last_success_time" => Ok(ctx.last_success_time.map(|x| x.to_string())),
+        "watermark" => Ok(ctx.watermark.clone()),
+        _ => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support dynamic value ${{{}}}", key)).to_result()
+    }
+}
+
+enum DynamicToken<'a> {
+    Text(&'a str),
+    Key(&'a str),
+}
+
+fn tokenize_dynamic_value(data : &'_ str) -> Result<Vec<DynamicToken<'_>>, CommonError> {
+    let mut tokens = Vec::new();
+    let mut rest = data;
+
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            tokens.push(DynamicToken::Text(&rest[..start - 1]));
+            tokens.push(DynamicToken::Text("${"));
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        let end = rest[start..].find('}').map(|x| x + start).ok_or_else(|| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("unterminated dynamic value {}", data))
+        })?;
+
+        tokens.push(DynamicToken::Text(&rest[..start]));
+        tokens.push(DynamicToken::Key(&rest[start + 2..end]));
+        rest = &rest[end + 1..];
+    }
+    tokens.push(DynamicToken::Text(rest));
+
+    Ok(tokens)
+}
+
+pub(super) fn resolve_dynamic_value(data : &'_ str, ctx : &'_ QueryRunContext) -> Result<Option<String>, CommonError> {
+    let tokens = tokenize_dynamic_value(data)?;
+
+    // a value that is a single ${key} keeps the key's None, so it binds NULL instead of an empty string
+    let keys : Vec<&str> = tokens.iter().filter_map(|x| match x {
+        DynamicToken::Key(k) => Some(*k),
+        DynamicToken::Text(_) => None
+    }).collect();
+    let only_blank_text = tokens.iter().all(|x| match x {
+        DynamicToken::Text(t) => t.trim().is_empty(),
+        DynamicToken::Key(_) => true
+    });
+    if keys.len() == 1 && only_blank_text {
+        return resolve_dynamic_key(keys[0], ctx);
+    }
+
+    let mut ret = String::with_capacity(data.len());
+    for token in tokens {
+        match token {
+            DynamicToken::Text(t) => ret.push_str(t),
+            DynamicToken::Key(k) => ret.push_str(resolve_dynamic_key(k, ctx)?.unwrap_or_default().as_str())
+        }
+    }
+
+    Ok(Some(ret))
+}
+
+fn days_from_civil(year : i64, month : i64, day : i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn parse_offset_millis(offset : &'_ str) -> Result<i64, CommonError> {
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let body = &offset[1..];
+    let parts : Vec<&str> = match (body.contains(':'), body.len()) {
+        _ if !body.is_ascii() => return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("offset {} not HH[:MM]", offset)).to_result(),
+        (true, _) => body.split(':').collect(),
+        (false, 2) => vec![body],
+        (false, 4) => vec![&body[..2], &body[2..]],
+        _ => return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("offset {} not HH[:MM]", offset)).to_result()
+    };
+    if parts.len() > 3 || parts.iter().any(|x| x.len() != 2) {
+        return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("offset {} not HH[:MM]", offset)).to_result();
+    }
+
+    let mut sec = 0;
+    for (part, unit) in parts.iter().zip([3600, 60, 1]) {
+        sec += part.parse::<i64>().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("offset {} parse failed : {}", offset, e))
+        })? * unit;
+    }
+    Ok(sign * sec * 1000)
+}
+
+pub(super) fn parse_timestamp_millis(data : &'_ str) -> Result<i64, CommonError> {
+    let s = data.trim();
+    if let Ok(millis) = s.parse::<i64>() {
+        return Ok(millis);
+    }
+
+    let parse_num = |x : &'_ str| {
+        x.parse::<i64>().map_err(|e| {
+            CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("timestamp {} parse failed : {}", data, e))
+        })
+    };
+
+    let s = s.strip_suffix(|c| c == 'Z' || c == 'z').unwrap_or(s);
+    let (date, time) = match s.split_once(|c| c == 'T' || c == ' ') {
+        Some((d, t)) => (d, Some(t)),
+        None => (s, None)
+    };
+
+    let ymd : Vec<&str> = date.split('-').collect();
+    if ymd.len() != 3 {
+        return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("timestamp {} not YYYY-MM-DD", data)).to_result();
+    }
+    let days = days_from_civil(parse_num(ymd[0])?, parse_num(ymd[1])?, parse_num(ymd[2])?);
+
+    let mut millis = 0;
+    if let Some(time) = time {
+        // rfc 3339 (+09:00) and postgres timestamptz text (+09, +0530, +05:30:00) offsets
+        let (time, offset_ms) = match time.find(|c| c == '+' || c == '-') {
+            Some(idx) => (&time[..idx], parse_offset_millis(&time[idx..]).map_err(|e| {
+                CommonError::extend(&CommonDefaultErrorKind::ParsingFail, format!("timestamp {} offset invalid", data), e)
+            })?),
+            None => (time, 0)
+        };
+
+        let (hms, frac) = time.split_once('.').unwrap_or((time, "0"));
+        let hms : Vec<&str> = hms.split(':').collect();
+        if hms.len() < 2 || hms.len() > 3 {
+            return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("timestamp {} not HH:MM[:SS]", data)).to_result();
+        }
+        let sec = parse_num(hms[0])? * 3600 + parse_num(hms[1])? * 60 + hms.get(2).map_or(Ok(0), |x| parse_num(x))?;
+        let frac_ms = parse_num(format!("{:0<3}", frac).get(..3).unwrap_or("0"))?;
+        millis = sec * 1000 + frac_ms - offset_ms;
+    }
+
+    Ok(days * 86_400_000 + millis)
+}
+
+pub(super) fn convert_str_value(data : Option<String>, type_name : Option<&'_ str>) -> Result<PairValueEnum, CommonError> {
+    let data = match data {
+        Some(d) => d,
+        None => return Ok(PairValueEnum::Null)
+    };
+
+    let parse_err = |t : &'_ str, e : String| {
+        CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("'{}' convert {} failed : {}", data, t, e))
+    };
+
+    let v = match type_name.map(|x| x.to_lowercase()).as_deref() {
+        None | Some("string") | Some("text") => PairValueEnum::String(data.clone()),
+        Some("int") => PairValueEnum::Int(data.trim().parse::<i32>().map_err(|e| parse_err("int", e.to_string()))?),
+        Some("bigint") => PairValueEnum::BigInt(data.trim().parse::<i64>().map_err(|e| parse_err("bigint", e.to_string()))?),
+        Some("double") => PairValueEnum::Double(data.trim().parse::<f64>().map_err(|e| parse_err("double", e.to_string()))?),
+        Some("bool") => match data.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" | "y" => PairValueEnum::Bool(true),
+            "false" | "0" | "no" | "n" => PairValueEnum::Bool(false),
+            _ => return Err(parse_err("bool", String::from("not bool literal")))
+        },
+        Some("null") => PairValueEnum::Null,
+        Some("bytes") => PairValueEnum::Bin(base64::engine::general_purpose::STANDARD.decode(data.trim()).map_err(|e| {
+            parse_err("bytes", e.to_string())
+        })?),
+        Some("timestamp_ms") => PairValueEnum::BigInt(parse_timestamp_millis(data.as_str()).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ParsingFail, "timestamp_ms convert failed", e)
+        })?),
+        Some("timestamp") => {
+            return CommonError::new(&CommonDefaultErrorKind::NoSupport, "type timestamp binds epoch millis, use timestamp_ms").to_result();
+        },
+        Some(other) => {
+            return CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support type {}", other)).to_result();
+        }
+    };
+
+    Ok(v)
+}
+
+pub(super) fn create_arg_value(arg : &'_ PlanChainArgs, ctx : &'_ QueryRunContext) -> Result<PairValueEnum, CommonError> {
+    let data = resolve_dynamic_value(arg.data.as_str(), ctx).map_err(|e| {
+        CommonError::extend(&CommonDefaultErrorKind::ParsingFail, format!("dynamic value failed {}", arg.data), e)
+    })?;
+
+    convert_str_value(data, arg.type_name.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_ctx() -> QueryRunContext {
+        QueryRunContext {
+            plan_name : String::from("copy"),
+            run_id : String::from("r1"),
+            scheduled_time : 0,
+            last_success_time : None,
+            watermark : None,
+        }
+    }
+
+    #[test]
+    fn escaped_dynamic_value_is_literal() {
+        let ctx = create_ctx();
+        assert_eq!(resolve_dynamic_value("$${HOME}", &ctx).unwrap().as_deref(), Some("${HOME}"));
+        assert_eq!(resolve_dynamic_value("a $${x} ${plan_name}", &ctx).unwrap().as_deref(), Some("a ${x} copy"));
+    }
+
+    #[test]
+    fn unknown_dynamic_value_fails() {
+        let ctx = create_ctx();
+        assert!(resolve_dynamic_value("${HOME}", &ctx).is_err());
+        assert_eq!(resolve_dynamic_value("${plan_name}", &ctx).unwrap().as_deref(), Some("copy"));
+    }
+
+    #[test]
+    fn mixed_template_is_tokenized() {
+        let ctx = create_ctx();
+        assert_eq!(resolve_dynamic_value("${plan_name} and {b}", &ctx).unwrap().as_deref(), Some("copy and {b}"));
+        assert_eq!(resolve_dynamic_value("${plan_name}-${run_id}", &ctx).unwrap().as_deref(), Some("copy-r1"));
+        assert_eq!(resolve_dynamic_value(" ${run_id} ", &ctx).unwrap().as_deref(), Some("r1"));
+    }
+
+    #[test]
+    fn last_success_time_empty_after_restart() {
+        let mut ctx = create_ctx();
+        assert!(resolve_dynamic_value("${last_success_time}", &ctx).unwrap().is_none());
+        assert_eq!(resolve_dynamic_value("t > '${last_success_time}'", &ctx).unwrap().as_deref(), Some("t > ''"));
+
+        ctx.last_success_time = Some(1000);
+        assert_eq!(resolve_dynamic_value("${last_success_time}", &ctx).unwrap().as_deref(), Some("1000"));
+    }
+
+    #[test]
+    fn timestamp_type_is_explicit_millis() {
+        assert!(matches!(convert_str_value(Some(String::from("1970-01-02")), Some("timestamp_ms")), Ok(PairValueEnum::BigInt(86_400_000))));
+        assert!(convert_str_value(Some(String::from("1970-01-02")), Some("timestamp")).is_err());
+    }
+
+    #[test]
+    fn timestamp_with_offset() {
+        let utc = parse_timestamp_millis("2024-01-02T03:04:05Z").unwrap();
+        assert_eq!(parse_timestamp_millis("2024-01-02T03:04:05+00:00").unwrap(), utc);
+        assert_eq!(parse_timestamp_millis("2024-01-02 03:04:05+00").unwrap(), utc);
+        assert_eq!(parse_timestamp_millis("2024-01-02T12:04:05+09:00").unwrap(), utc);
+        assert_eq!(parse_timestamp_millis("2024-01-02 12:04:05+09").unwrap(), utc);
+        assert_eq!(parse_timestamp_millis("2024-01-01 22:04:05-0500").unwrap(), utc);
+        assert_eq!(parse_timestamp_millis("2024-01-02 08:34:05.250+05:30").unwrap(), utc + 250);
+        assert!(parse_timestamp_millis("2024-01-02 03:04:05+9").is_err());
+        assert!(parse_timestamp_millis("2024-01-02 03:04:05+09:0a").is_err());
+    }
+}
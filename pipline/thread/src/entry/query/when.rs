@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Dot,
+}
+
+#[derive(Debug, Clone)]
+enum WhenValue {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+}
+
+impl WhenValue {
+    fn from_pair(v : &'_ PairValueEnum) -> WhenValue {
+        match v {
+            PairValueEnum::Null => WhenValue::Null,
+            PairValueEnum::Bool(b) => WhenValue::Bool(*b),
+            PairValueEnum::Int(i) => WhenValue::Num(*i as f64),
+            PairValueEnum::BigInt(i) => WhenValue::Num(*i as f64),
+            PairValueEnum::Float(f) => WhenValue::Num(*f as f64),
+            PairValueEnum::Double(d) => WhenValue::Num(*d),
+            PairValueEnum::String(s) => WhenValue::Str(s.clone()),
+            PairValueEnum::Bin(b) => WhenValue::Str(String::from_utf8_lossy(b.as_slice()).to_string()),
+            PairValueEnum::Array(_) | PairValueEnum::Map(_) => WhenValue::Null,
+        }
+    }
+
+    fn as_num(&self) -> Option<f64> {
+        match self {
+            WhenValue::Num(n) => Some(*n),
+            WhenValue::Bool(b) => Some(if *b {1.0} else {0.0}),
+            WhenValue::Str(s) => s.trim().parse::<f64>().ok(),
+            WhenValue::Null => None,
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            WhenValue::Null => false,
+            WhenValue::Bool(b) => *b,
+            WhenValue::Num(n) => *n != 0.0,
+            WhenValue::Str(s) => !s.is_empty(),
+        }
+    }
+}
+
+impl std::fmt::Display for WhenValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WhenValue::Null => write!(f, "null"),
+            WhenValue::Bool(b) => write!(f, "{}", b),
+            WhenValue::Num(n) => write!(f, "{}", n),
+            WhenValue::Str(s) => write!(f, "'{}'", s),
+        }
+    }
+}
+
+fn tokenize(expr : &'_ str) -> Result<Vec<Token>, CommonError> {
+    let chars : Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+        if c.is_whitespace() {
+            idx += 1;
+            continue;
+        }
+
+        match c {
+            '(' => { tokens.push(Token::LParen); idx += 1; },
+            ')' => { tokens.push(Token::RParen); idx += 1; },
+            '[' => { tokens.push(Token::LBracket); idx += 1; },
+            ']' => { tokens.push(Token::RBracket); idx += 1; },
+            '.' => { tokens.push(Token::Dot); idx += 1; },
+            '\'' | '"' => {
+                let quote = c;
+                let start = idx + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end] != quote {
+                    end += 1;
+                }
+                if end >= chars.len() {
+                    return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("unterminated string at {}", idx)).to_result();
+                }
+                tokens.push(Token::Str(chars[start..end].iter().collect()));
+                idx = end + 1;
+            },
+            '=' | '!' | '<' | '>' | '&' | '|' => {
+                let next = chars.get(idx + 1).cloned();
+                let op = match (c, next) {
+                    ('=', Some('=')) => "==",
+                    ('!', Some('=')) => "!=",
+                    ('<', Some('=')) => "<=",
+                    ('>', Some('=')) => ">=",
+                    ('&', Some('&')) => "and",
+                    ('|', Some('|')) => "or",
+                    ('<', _) => "<",
+                    ('>', _) => ">",
+                    ('!', _) => "not",
+                    ('=', _) => "==",
+                    _ => {
+                        return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("unknown operator at {}", idx)).to_result();
+                    }
+                };
+                idx += match op {
+                    "<" | ">" | "not" => 1,
+                    "==" if next != Some('=') => 1,
+                    _ => 2
+                };
+                tokens.push(Token::Op(op));
+            },
+            _ if c.is_ascii_digit() || (c == '-' && chars.get(idx + 1).map_or(false, |x| x.is_ascii_digit())) => {
+                let start = idx;
+                idx += 1;
+                while idx < chars.len() && (chars[idx].is_ascii_digit() || chars[idx] == '.') {
+                    idx += 1;
+                }
+                let s : String = chars[start..idx].iter().collect();
+                let n = s.parse::<f64>().map_err(|e| {
+                    CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("number {} parse failed: {}", s, e))
+                })?;
+                tokens.push(Token::Number(n));
+            },
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = idx;
+                while idx < chars.len() && (chars[idx].is_alphanumeric() || chars[idx] == '_' || chars[idx] == '-') {
+                    idx += 1;
+                }
+                let s : String = chars[start..idx].iter().collect();
+                match s.to_lowercase().as_str() {
+                    "and" => tokens.push(Token::Op("and")),
+                    "or" => tokens.push(Token::Op("or")),
+                    "not" => tokens.push(Token::Op("not")),
+                    _ => tokens.push(Token::Ident(s))
+                }
+            },
+            _ => {
+                return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("unexpected char '{}' at {}", c, idx)).to_result();
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+pub(super) struct WhenEvaluator<'a> {
+    tokens : Vec<Token>,
+    pos : usize,
+    data_map : &'a HashMap<String, PairValueEnum>,
+    trace : Vec<String>,
+}
+
+impl<'a> WhenEvaluator<'a> {
+    pub fn eval(expr : &'_ str, data_map : &'a HashMap<String, PairValueEnum>) -> Result<(bool, String), CommonError> {
+        let tokens = tokenize(expr).map_err(|e| {
+            CommonError::extend(&CommonDefaultErrorKind::ParsingFail, format!("when tokenize failed `{}`", expr), e)
+        })?;
+
+        let mut evaluator = WhenEvaluator { tokens, pos : 0, data_map, trace : Vec::new() };
+        let ret = evaluator.parse_or()?;
+
+        if evaluator.pos != evaluator.tokens.len() {
+            return CommonError::new(&CommonDefaultErrorKind::ParsingFail,
+                                    format!("when unexpected token {:?} in `{}`", evaluator.tokens[evaluator.pos], expr)).to_result();
+        }
+
+        Ok((ret.truthy(), evaluator.trace.join(", ")))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn expect(&mut self, t : Token) -> Result<(), CommonError> {
+        match self.next() {
+            Some(ref n) if *n == t => Ok(()),
+            other => CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("expected {:?}, got {:?}", t, other)).to_result()
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<WhenValue, CommonError> {
+        let mut left = self.parse_and()?;
+        while self.peek() == Some(&Token::Op("or")) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = WhenValue::Bool(left.truthy() || right.truthy());
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<WhenValue, CommonError> {
+        let mut left = self.parse_not()?;
+        while self.peek() == Some(&Token::Op("and")) {
+            self.pos += 1;
+            let right = self.parse_not()?;
+            left = WhenValue::Bool(left.truthy() && right.truthy());
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<WhenValue, CommonError> {
+        if self.peek() == Some(&Token::Op("not")) {
+            self.pos += 1;
+            let v = self.parse_not()?;
+            return Ok(WhenValue::Bool(!v.truthy()));
+        }
+        self.parse_cmp()
+    }
+
+    fn parse_cmp(&mut self) -> Result<WhenValue, CommonError> {
+        let left = self.parse_operand()?;
+        let op = match self.peek() {
+            Some(Token::Op(op)) if ["==", "!=", "<", "<=", ">", ">="].contains(op) => *op,
+            _ => return Ok(left)
+        };
+        self.pos += 1;
+        let right = self.parse_operand()?;
+
+        let ord = match (left.as_num(), right.as_num(), &left, &right) {
+            (_, _, WhenValue::Null, WhenValue::Null) => Some(std::cmp::Ordering::Equal),
+            (_, _, WhenValue::Null, _) | (_, _, _, WhenValue::Null) => None,
+            (Some(l), Some(r), _, _) => l.partial_cmp(&r),
+            (_, _, WhenValue::Str(l), WhenValue::Str(r)) => Some(l.cmp(r)),
+            _ => None
+        };
+
+        let ret = match (op, ord) {
+            ("==", Some(o)) => o.is_eq(),
+            ("!=", Some(o)) => o.is_ne(),
+            ("!=", None) => true,
+            ("<", Some(o)) => o.is_lt(),
+            ("<=", Some(o)) => o.is_le(),
+            (">", Some(o)) => o.is_gt(),
+            (">=", Some(o)) => o.is_ge(),
+            _ => false
+        };
+
+        Ok(WhenValue::Bool(ret))
+    }
+
+    fn parse_operand(&mut self) -> Result<WhenValue, CommonError> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let v = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(v)
+            },
+            Some(Token::Number(n)) => Ok(WhenValue::Num(n)),
+            Some(Token::Str(s)) => Ok(WhenValue::Str(s)),
+            Some(Token::Ident(ident)) => {
+                match ident.to_lowercase().as_str() {
+                    "true" => return Ok(WhenValue::Bool(true)),
+                    "false" => return Ok(WhenValue::Bool(false)),
+                    "null" => return Ok(WhenValue::Null),
+                    _ => {}
+                }
+
+                if ident == "rows" && self.peek() == Some(&Token::LParen) {
+                    self.pos += 1;
+                    let id = match self.next() {
+                        Some(Token::Ident(id)) => id,
+                        other => return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("rows() expect step id, got {:?}", other)).to_result()
+                    };
+                    self.expect(Token::RParen)?;
+
+                    let cnt = self.data_map.get(id.as_str()).map_or(0, |v| super::get_ret_bind_param_cnt(v));
+                    self.trace.push(format!("rows({})={}", id, cnt));
+                    return Ok(WhenValue::Num(cnt as f64));
+                }
+
+                self.expect(Token::Dot)?;
+                let col = match self.next() {
+                    Some(Token::Ident(col)) => col,
+                    other => return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("{}. expect column, got {:?}", ident, other)).to_result()
+                };
+
+                let mut row = 0;
+                if self.peek() == Some(&Token::LBracket) {
+                    self.pos += 1;
+                    row = match self.next() {
+                        Some(Token::Number(n)) if n >= 0.0 => n as usize,
+                        other => return CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("{}.{}[] expect row, got {:?}", ident, col, other)).to_result()
+                    };
+                    self.expect(Token::RBracket)?;
+                }
+
+                let v = match self.data_map.get(ident.as_str()) {
+                    Some(PairValueEnum::Map(m)) => match m.get(col.as_str()) {
+                        Some(PairValueEnum::Array(arr)) => arr.get(row).map_or(WhenValue::Null, WhenValue::from_pair),
+                        Some(other) if row == 0 => WhenValue::from_pair(other),
+                        _ => WhenValue::Null
+                    },
+                    _ => WhenValue::Null
+                };
+                self.trace.push(format!("{}.{}[{}]={}", ident, col, row, v));
+                Ok(v)
+            },
+            other => CommonError::new(&CommonDefaultErrorKind::ParsingFail, format!("unexpected operand {:?}", other)).to_result()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_data_map() -> HashMap<String, PairValueEnum> {
+        let mut step = HashMap::new();
+        step.insert(String::from("cnt"), PairValueEnum::Array(vec![PairValueEnum::String(String::from("10")), PairValueEnum::String(String::from("9"))]));
+        step.insert(String::from("name"), PairValueEnum::Array(vec![PairValueEnum::String(String::from("abc")), PairValueEnum::Null]));
+        let mut scalar = HashMap::new();
+        scalar.insert(String::from("total"), PairValueEnum::BigInt(3));
+
+        let mut data_map = HashMap::new();
+        data_map.insert(String::from("s1"), PairValueEnum::Map(step));
+        data_map.insert(String::from("s2"), PairValueEnum::Map(scalar));
+        data_map
+    }
+
+    fn eval(expr : &'_ str) -> bool {
+        WhenEvaluator::eval(expr, &create_data_map()).expect("when eval failed").0
+    }
+
+    #[test]
+    fn precedence() {
+        assert!(eval("true or false and false"));
+        assert!(!eval("(true or false) and false"));
+        assert!(eval("not false and true"));
+        assert!(!eval("not (false or true)"));
+        assert!(eval("1 < 2 && 2 >= 2 || 0"));
+    }
+
+    #[test]
+    fn numeric_strings_compare_as_numbers() {
+        assert!(eval("s1.cnt[0] > s1.cnt[1]"));
+        assert!(eval("s1.cnt > 9"));
+        assert!(eval("'10' > '9'"));
+        assert!(eval("'b' > 'abc'"));
+    }
+
+    #[test]
+    fn rows_and_index() {
+        assert!(eval("rows(s1) == 2"));
+        assert!(eval("rows(none) == 0"));
+        assert!(eval("s1.name[0] == 'abc'"));
+        assert!(eval("s1.name[1] == null"));
+        assert!(eval("s1.name[5] == null"));
+        assert!(eval("s2.total == 3"));
+        assert!(eval("s2.total[1] == null"));
+        assert!(eval("s1.missing != 1"));
+    }
+
+    #[test]
+    fn trace_reports_values() {
+        let (ret, reason) = WhenEvaluator::eval("rows(s1) > 1 and s1.cnt[1] == 9", &create_data_map()).unwrap();
+        assert!(ret);
+        assert_eq!(reason, "rows(s1)=2, s1.cnt[1]='9'");
+    }
+
+    #[test]
+    fn error_cases() {
+        let data_map = create_data_map();
+        for expr in ["'abc", "(true", "true false", "rows(1)", "s1.", "s1.cnt[-1]", "s1.cnt[0", "a # b", "a & b", ""] {
+            assert!(WhenEvaluator::eval(expr, &data_map).is_err(), "{} must fail", expr);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use common_rs::c_err::CommonError;
+use common_rs::c_err::gen::CommonDefaultErrorKind;
+use common_rs::exec::interfaces::pair::PairValueEnum;
+use mypip_types::config::plan::PlanWatermark;
+
+fn compare_value(a : &'_ PairValueEnum, b : &'_ PairValueEnum) -> Option<Ordering> {
+    match (a, b) {
+        (PairValueEnum::Int(x), PairValueEnum::Int(y)) => Some(x.cmp(y)),
+        (PairValueEnum::BigInt(x), PairValueEnum::BigInt(y)) => Some(x.cmp(y)),
+        (PairValueEnum::Int(x), PairValueEnum::BigInt(y)) => Some((*x as i64).cmp(y)),
+        (PairValueEnum::BigInt(x), PairValueEnum::Int(y)) => Some(x.cmp(&(*y as i64))),
+        (PairValueEnum::Double(x), PairValueEnum::Double(y)) => x.partial_cmp(y),
+        (PairValueEnum::String(x), PairValueEnum::String(y)) => Some(x.cmp(y)),
+        _ => None
+    }
+}
+
+fn convert_watermark_str(v : &'_ PairValueEnum) -> Result<String, CommonError> {
+    match v {
+        PairValueEnum::Int(x) => Ok(x.to_string()),
+        PairValueEnum::BigInt(x) => Ok(x.to_string()),
+        PairValueEnum::Double(x) => Ok(x.to_string()),
+        PairValueEnum::String(x) => Ok(x.clone()),
+        other => CommonError::new(&CommonDefaultErrorKind::NoSupport, format!("not support watermark value {:?}", other)).to_result()
+    }
+}
+
+fn find_max<'d>(wm : &'_ PlanWatermark, data_map : &'d HashMap<String, PairValueEnum>) -> Result<Option<&'d PairValueEnum>, CommonError> {
+    let cols = match data_map.get(wm.step.as_str()) {
+        Some(PairValueEnum::Map(cols)) => cols,
+        Some(PairValueEnum::Null) | None => return Ok(None),
+        Some(other) => {
+            return CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("watermark step {} not map {:?}", wm.step, other)).to_result();
+        }
+    };
+
+    let values = match cols.get(wm.column.as_str()) {
+        Some(PairValueEnum::Array(arr)) => arr.iter().collect::<Vec<&PairValueEnum>>(),
+        Some(other) => vec![other],
+        None => {
+            return CommonError::new(&CommonDefaultErrorKind::NoData, format!("watermark column {}.{} not exists", wm.step, wm.column)).to_result();
+        }
+    };
+
+    let mut max : Option<&PairValueEnum> = None;
+    for v in values.into_iter().filter(|x| !matches!(x, PairValueEnum::Null)) {
+        max = max_value(max, v)?;
+    }
+    Ok(max)
+}
+
+fn max_value<'d>(max : Option<&'d PairValueEnum>, v : &'d PairValueEnum) -> Result<Option<&'d PairValueEnum>, CommonError> {
+    match max {
+        None => Ok(Some(v)),
+        Some(m) => match compare_value(v, m) {
+            Some(Ordering::Greater) => Ok(Some(v)),
+            Some(_) => Ok(Some(m)),
+            None => CommonError::new(&CommonDefaultErrorKind::NotMatchArgs, format!("watermark {:?} not comparable {:?}", v, m)).to_result()
+        }
+    }
+}
+
+// paginated chains run the downstream steps per page, so the max is kept across every page map
+pub(crate) struct WatermarkMax<'w> {
+    wm : &'w PlanWatermark,
+    max : Option<PairValueEnum>,
+}
+
+impl<'w> WatermarkMax<'w> {
+    pub fn new(wm : &'w PlanWatermark) -> Self {
+        WatermarkMax { wm, max : None }
+    }
+
+    pub fn push(&mut self, data_map : &HashMap<String, PairValueEnum>) -> Result<(), CommonError> {
+        if let Some(v) = find_max(self.wm, data_map)? {
+            let next = max_value(self.max.as_ref(), v)?.cloned();
+            self.max = next;
+        }
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<Option<String>, CommonError> {
+        self.max.as_ref().map(convert_watermark_str).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_page(values : Vec<PairValueEnum>) -> HashMap<String, PairValueEnum> {
+        let mut cols = HashMap::new();
+        cols.insert(String::from("id"), PairValueEnum::Array(values));
+        let mut data_map = HashMap::new();
+        data_map.insert(String::from("src"), PairValueEnum::Map(cols));
+        data_map
+    }
+
+    fn create_wm() -> PlanWatermark {
+        PlanWatermark { step : String::from("src"), column : String::from("id"), store : String::from("file"), path : None, initial : None }
+    }
+
+    #[test]
+    fn max_kept_across_pages() {
+        let wm = create_wm();
+        let mut tracker = WatermarkMax::new(&wm);
+
+        tracker.push(&create_page(vec![PairValueEnum::BigInt(3), PairValueEnum::BigInt(9)])).unwrap();
+        tracker.push(&create_page(vec![PairValueEnum::BigInt(5), PairValueEnum::Null])).unwrap();
+        tracker.push(&HashMap::new()).unwrap();
+        assert_eq!(tracker.finish().unwrap().as_deref(), Some("9"));
+    }
+
+    #[test]
+    fn empty_pages_keep_none() {
+        let wm = create_wm();
+        let mut tracker = WatermarkMax::new(&wm);
+
+        tracker.push(&create_page(Vec::new())).unwrap();
+        assert_eq!(tracker.finish().unwrap(), None);
+
+        tracker.push(&create_page(vec![PairValueEnum::String(String::from("a"))])).unwrap();
+        assert!(tracker.push(&create_page(vec![PairValueEnum::BigInt(1)])).is_err());
+    }
+}
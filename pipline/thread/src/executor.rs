@@ -18,7 +18,8 @@ use mypip_types::interface::GlobalLayout;
 
 pub struct PlanThreadExecutorCancel {
     exec : Arc<PlanThreadExecutor>,
-    join_handle: Option<JoinHandle<()>>
+    join_handle: Option<JoinHandle<()>>,
+    health_handle: Option<JoinHandle<()>>
 }
 pub struct PlanThreadExecutor {
     manager  : Arc<dyn SimpleThreadManager<PlanThreadEntry> + Send + Sync>,
@@ -36,6 +37,9 @@ impl PlanThreadExecutorCancel {
         if !self.exec.stop_flag.swap(true, Ordering::SeqCst) {
             let take = self.join_handle.take();
             take.expect("plan thread executor cancel take is broken").join().expect("join is broken");
+            if let Some(health) = self.health_handle.take() {
+                health.join().expect("health join is broken");
+            }
         }
     }
 }
@@ -157,6 +161,16 @@ impl PlanThreadExecutor {
         Ok(())
     }
 
+    fn health_loop(&self) {
+        while !self.stop_flag.load(Ordering::Relaxed) {
+            self.next_sleep();
+
+            if let Err(e) = GLOBAL.probe_connections() {
+                log_error!("thread_executor", "{}", CommonError::extend(&CommonDefaultErrorKind::Etc, "probe failed connections", e));
+            }
+        }
+    }
+
     pub fn daemon() -> PlanThreadExecutorCancel {
         let exec = Arc::new(PlanThreadExecutor {
             manager: new_simple_thread_manager(SimpleManagerKind::Pool, 100),
@@ -174,6 +188,12 @@ impl PlanThreadExecutor {
             log_info!("thread_executor", "stop daemon");
         });
 
-        PlanThreadExecutorCancel {exec, join_handle: Some(join)}
+        let health_exec = exec.clone();
+        let health = std::thread::spawn(move || {
+            health_exec.health_loop();
+            log_info!("thread_executor", "stop health probe");
+        });
+
+        PlanThreadExecutorCancel {exec, join_handle: Some(join), health_handle: Some(health)}
     }
 }
\ No newline at end of file
@@ -55,4 +55,12 @@ impl PlanThreadStateRunSet {
         writer.remove(name);
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+pub struct QueryRunContext {
+    pub plan_name : String,
+    pub run_id : String,
+    pub scheduled_time : u128,
+    pub last_success_time : Option<u128>,
+    pub watermark : Option<String>,
+}
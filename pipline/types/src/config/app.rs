@@ -1,6 +1,13 @@
 use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
+use crate::config::conn::ConnectionTlsInfo;
 
+const fn interpreter_enable_default() -> bool {
+    true
+}
+const fn interpreter_pool_size_default() -> usize {
+    100
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppDbConnectionConfig {
@@ -9,7 +16,8 @@ pub struct AppDbConnectionConfig {
     pub db_name : String,
     pub db_user : String,
     pub db_password : String,
-    pub db_ttl : u64
+    pub db_ttl : u64,
+    pub tls : Option<ConnectionTlsInfo>
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppLogConfig {
@@ -23,6 +31,29 @@ pub struct AppLogConfig {
     pub log_db_config: Option<AppDbConnectionConfig>
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppInterpreterLangConfig {
+    #[serde(default = "interpreter_enable_default")]
+    pub enable : bool,
+    #[serde(default = "interpreter_pool_size_default")]
+    pub pool_size : usize
+}
+
+impl Default for AppInterpreterLangConfig {
+    fn default() -> Self {
+        AppInterpreterLangConfig {
+            enable : interpreter_enable_default(),
+            pool_size : interpreter_pool_size_default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AppInterpreterConfig {
+    pub lua : Option<AppInterpreterLangConfig>,
+    pub python : Option<AppInterpreterLangConfig>
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     #[serde(alias = "log")]
@@ -30,5 +61,9 @@ pub struct AppConfig {
     #[serde(alias = "script_lib")]
     pub script_lib : Option<String>,
     #[serde(alias = "db_config")]
-    pub db_config : Option<AppDbConnectionConfig>
+    pub db_config : Option<AppDbConnectionConfig>,
+    #[serde(alias = "secret_key")]
+    pub secret_key_file : Option<String>,
+    #[serde(alias = "interpreter")]
+    pub interpreter_conf : Option<AppInterpreterConfig>
 }
\ No newline at end of file
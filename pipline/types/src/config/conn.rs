@@ -1,14 +1,59 @@
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 
+const fn health_interval_sec_default() -> u64 {
+    30
+}
+const fn health_failure_threshold_default() -> u32 {
+    3
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OdbcConnectionInfo {
-    pub driver : String,
+    pub driver : Option<String>,
     pub current_time_query : String,
-    pub current_time_col_name : String
+    pub current_time_col_name : String,
+
+    #[serde(alias = "conn_str")]
+    pub connection_string : Option<String>,
+    pub dsn : Option<String>,
+    pub attributes : Option<HashMap<String, String>>
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionHealthInfo {
+    #[serde(default = "health_interval_sec_default")]
+    pub interval_sec : u64,
+    #[serde(default = "health_failure_threshold_default")]
+    pub failure_threshold : u32,
+    pub ping_query : Option<String>
+}
+impl Default for ConnectionHealthInfo {
+    fn default() -> Self {
+        ConnectionHealthInfo {
+            interval_sec : health_interval_sec_default(),
+            failure_threshold : health_failure_threshold_default(),
+            ping_query : None
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct ConnectionTlsInfo {
+    #[serde(alias = "mode")]
+    pub sslmode : Option<String>,
+    #[serde(alias = "ca")]
+    pub ca_file : Option<String>,
+    #[serde(alias = "cert")]
+    pub cert_file : Option<String>,
+    #[serde(alias = "key")]
+    pub key_file : Option<String>,
+    pub server_name : Option<String>
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct ConnectionTopologyInfo {
+    pub replicas : Option<Vec<String>>,
+    pub target_session_attrs : Option<String>
+}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ConnectionInfo {
     #[serde(alias = "max_conn")]
     pub max_size : usize,
@@ -26,8 +71,19 @@ pub struct ConnectionInfo {
     #[serde(alias = "timeout")]
     pub conn_timeout : u32,
 
+    pub min_idle : Option<usize>,
+    pub idle_timeout_sec : Option<u64>,
+    pub max_lifetime_sec : Option<u64>,
+    pub acquire_timeout_ms : Option<u64>,
+
     #[serde(alias = "odbc")]
-    pub odbc : Option<OdbcConnectionInfo>
+    pub odbc : Option<OdbcConnectionInfo>,
+
+    pub health : Option<ConnectionHealthInfo>,
+
+    pub topology : Option<ConnectionTopologyInfo>,
+
+    pub tls : Option<ConnectionTlsInfo>
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
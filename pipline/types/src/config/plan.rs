@@ -4,25 +4,56 @@ use serde::{Serialize, Deserialize};
 const fn plan_enable_default() -> bool {
     true
 }
+pub fn plan_watermark_store_default() -> String {
+    String::from("db")
+}
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanChainBindParam {
+    #[serde(default)]
     pub idx : usize,
     pub key : String,
     pub id : String,
-    pub row : Option<usize>
+    pub row : Option<usize>,
+    pub name : Option<String>,
+    pub cast : Option<String>
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanChainArgs {
     pub data  : String,
-    pub idx   : usize
+    #[serde(default)]
+    pub idx   : usize,
+    pub name  : Option<String>,
+    #[serde(alias = "type")]
+    pub type_name : Option<String>
 }
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PlanChain {
     pub id : String,
+    #[serde(default)]
     pub connection: String,
+    #[serde(default)]
     pub query: String,
     pub bind : Option<Vec<PlanChainBindParam>>,
-    pub args : Option<Vec<PlanChainArgs>>
+    pub args : Option<Vec<PlanChainArgs>>,
+    pub when : Option<String>,
+    pub mode : Option<String>,
+    pub batch_size : Option<usize>,
+    pub source_row_col : Option<String>,
+    pub tx_group : Option<String>,
+    pub kind : Option<String>,
+    pub script : Option<PlanScript>,
+    pub func : Option<String>,
+    pub after : Option<Vec<String>>,
+    pub bind_mode : Option<String>,
+    pub lookup_key : Option<Vec<String>>,
+    pub sink : Option<PlanSink>,
+    #[serde(alias = "assert")]
+    pub assertion : Option<PlanAssert>,
+    pub paginate : Option<PlanPaginate>,
+    pub redis : Option<PlanRedis>,
+    pub on_row_error : Option<String>,
+    pub dead_letter : Option<PlanDeadLetter>,
+    pub read_only : Option<bool>
 }
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct PlanInterval {
@@ -36,6 +67,85 @@ pub struct PlanScript  {
     pub file : String
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanSink {
+    pub step : String,
+    pub format : String,
+    pub path : String,
+    pub columns : Option<Vec<String>>,
+    #[serde(default)]
+    pub gzip : bool,
+    pub rotate_rows : Option<usize>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanAssertRange {
+    pub column : String,
+    pub min : Option<f64>,
+    pub max : Option<f64>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanAssertFreshness {
+    pub column : String,
+    pub max_age_sec : u64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanAssert {
+    pub step : String,
+    pub min_rows : Option<usize>,
+    pub max_rows : Option<usize>,
+    pub not_null : Option<Vec<String>>,
+    pub unique : Option<Vec<String>>,
+    pub range : Option<Vec<PlanAssertRange>>,
+    pub freshness : Option<PlanAssertFreshness>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanPaginate {
+    pub mode : String,
+    pub page_size : usize,
+    pub column : Option<String>,
+    pub initial : Option<String>,
+    #[serde(alias = "type")]
+    pub type_name : Option<String>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanRedisField {
+    pub field : String,
+    pub column : String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanRedis {
+    pub step : String,
+    pub command : String,
+    pub key : String,
+    pub value : Option<String>,
+    pub fields : Option<Vec<PlanRedisField>>,
+    pub ttl_sec : Option<u64>,
+    pub pipeline : Option<usize>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanDeadLetter {
+    pub connection : Option<String>,
+    pub table : Option<String>,
+    pub path : Option<String>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanWatermark {
+    pub step : String,
+    pub column : String,
+    #[serde(default = "plan_watermark_store_default")]
+    pub store : String,
+    pub path : Option<String>,
+    pub initial : Option<String>
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Plan {
     pub interval : PlanInterval,
@@ -43,9 +153,12 @@ pub struct Plan {
     pub type_name : String,
     #[serde(default = "plan_enable_default")]
     pub enable : bool,
+    #[serde(default)]
+    pub transaction : bool,
     
     pub script    : Option<PlanScript>,
-    pub chain     : Option<Vec<PlanChain>>
+    pub chain     : Option<Vec<PlanChain>>,
+    pub watermark : Option<PlanWatermark>
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
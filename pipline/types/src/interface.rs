@@ -2,11 +2,11 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
 use common_rs::c_core::collection::pool::ThreadSafePool;
-use crate::config::plan::{Plan, PlanRoot};
-use common_rs::exec::interfaces::pair::PairExecutorPool;
+use crate::config::plan::{Plan, PlanRoot, PlanWatermark};
+use common_rs::exec::interfaces::pair::{PairExecutorPool, PairValueEnum};
 use common_rs::c_err::CommonError;
 use common_rs::init::InitConfig;
-use crate::config::conn::ConnectionInfos;
+use crate::config::conn::{ConnectionInfo, ConnectionInfos};
 use crate::typealias::InterpreterPool;
 use crate::config::app::AppConfig;
 
@@ -15,11 +15,15 @@ pub trait ConfLoader : Send + Sync {
     fn load_connection(&self) -> Result<ConnectionInfos, CommonError>;
 
     fn load_script_data(&self) -> Result<HashMap<String, String>, CommonError>;
+
+    fn load_watermark(&self, plan_name : &'_ str) -> Result<Option<String>, CommonError>;
+    fn save_watermark(&self, plan_name : &'_ str, value : &'_ str) -> Result<(), CommonError>;
 }
 
 pub trait Interpreter {
     fn gc(&self)  -> Result<(),CommonError>;
     fn run(&self, name : &'_ str) -> Result<(),CommonError>;
+    fn transform(&self, name : &'_ str, func : &'_ str, data : &'_ PairValueEnum) -> Result<PairValueEnum, CommonError>;
 }
 
 pub trait GlobalLayoutInit : Sync {
@@ -27,10 +31,20 @@ pub trait GlobalLayoutInit : Sync {
 }
 pub trait GlobalLayout : Sync {
     fn get_exec_pool(&'static self, name : Cow<'_, str>) -> Result<PairExecutorPool, CommonError >;
+    fn get_read_exec_pool(&'static self, name : Cow<'_, str>) -> Result<PairExecutorPool, CommonError >;
+    fn failover_exec_pool(&'static self, name : Cow<'_, str>, read_only : bool) -> Result<bool, CommonError>;
+    fn get_conn_info(&'static self, name : Cow<'_, str>) -> Result<ConnectionInfo, CommonError>;
     fn get_plan(&'static self) -> Result<HashMap<String, Plan>, CommonError>;
     fn get_interpreter_pool(&'static self, name : Cow<'_, str>) -> Result<InterpreterPool, CommonError>;
     fn close(&'static self) -> Result<(), CommonError>;
     fn reset(&'static self) -> Result<(), CommonError>;
     fn get_script_data(&'static self, name : &'_ str) -> Result<String, CommonError>;
     fn get_script_lib_path(&'static self) -> Result<Option<String>, CommonError>;
+    fn load_watermark(&'static self, plan_name : &'_ str, wm : &'_ PlanWatermark) -> Result<Option<String>, CommonError>;
+    fn save_watermark(&'static self, plan_name : &'_ str, wm : &'_ PlanWatermark, value : &'_ str) -> Result<(), CommonError>;
+    fn is_conn_open_circuit(&'static self, name : Cow<'_, str>) -> Result<bool, CommonError>;
+    fn report_conn_result(&'static self, name : Cow<'_, str>, ok : bool) -> Result<(), CommonError>;
+    fn probe_connections(&'static self) -> Result<(), CommonError>;
+    fn acquire_conn_slot(&'static self, name : Cow<'_, str>) -> Result<bool, CommonError>;
+    fn release_conn_slot(&'static self, name : Cow<'_, str>) -> Result<(), CommonError>;
 }